@@ -0,0 +1,191 @@
+use serenity::{
+    async_trait, builder::CreateInteractionResponseData, client::Context,
+    model::application::interaction::application_command::ApplicationCommandInteraction,
+    model::prelude::*,
+};
+use time::OffsetDateTime;
+
+use crate::consts::{CONFIG, DATABASE};
+use crate::db::{Job, ScheduledJob};
+use crate::features::scheduler;
+use bridge_scrims::interaction::*;
+
+/// Most jobs listed at once, the embed description fits 4096 characters
+const MAX_JOBS: usize = 20;
+
+/// Shows and manages the jobs of the scheduler
+pub struct Jobs;
+
+async fn find_job(id: u64) -> crate::Result<ScheduledJob> {
+    Ok(DATABASE
+        .run(|db| db.fetch_jobs())
+        .await?
+        .into_iter()
+        .find(|job| job.id == id)
+        .ok_or_else(|| ErrorResponse::message(format!("There is no job #{}.", id)))?)
+}
+
+fn describe(job: &ScheduledJob) -> String {
+    let details = match job.job {
+        Job::TicketTimeout {
+            channel,
+            in_question,
+            ..
+        } => format!(
+            " {} for {}",
+            ChannelId(channel).mention(),
+            UserId(in_question).mention()
+        ),
+        Job::FreezeDeadline { user, .. } | Job::FreezeCleanup { user, .. } => {
+            format!(" for {}", UserId(user).mention())
+        }
+        _ => String::new(),
+    };
+    let state = if job.failed {
+        String::from("**Failed**")
+    } else if job.locked_until.is_some() {
+        String::from("Running")
+    } else {
+        format!("<t:{}:R>", job.run_at.unix_timestamp())
+    };
+    let mut line = format!(
+        "`#{}` **{}**{} • {}",
+        job.id,
+        job.job.name(),
+        details,
+        state
+    );
+    if let Some(error) = &job.last_error {
+        let error: String = error.chars().take(100).collect();
+        line.push_str(&format!(
+            "\n> {} attempt(s), last error: `{}`",
+            job.attempts, error
+        ));
+    }
+    line
+}
+
+#[async_trait]
+impl InteractionHandler for Jobs {
+    fn name(&self) -> String {
+        String::from("jobs")
+    }
+
+    // Administrators only
+    fn allowed_roles(&self) -> Option<Vec<RoleId>> {
+        Some(Vec::new())
+    }
+
+    async fn register(&self, ctx: &Context) -> crate::Result<()> {
+        CONFIG
+            .guild
+            .create_application_command(&ctx, |c| {
+                c.name(self.name())
+                    .description("Manages scheduled jobs")
+                    .default_member_permissions(Permissions::empty())
+                    .create_option(|list| {
+                        list.kind(command::CommandOptionType::SubCommand)
+                            .name("list")
+                            .description("Shows the scheduled jobs")
+                    })
+                    .create_option(|retry| {
+                        retry
+                            .kind(command::CommandOptionType::SubCommand)
+                            .name("retry")
+                            .description("Runs a job right away, even if it failed")
+                            .create_sub_option(|o| {
+                                o.name("id")
+                                    .description("The job id")
+                                    .required(true)
+                                    .kind(command::CommandOptionType::Integer)
+                            })
+                    })
+                    .create_option(|cancel| {
+                        cancel
+                            .kind(command::CommandOptionType::SubCommand)
+                            .name("cancel")
+                            .description("Removes a job")
+                            .create_sub_option(|o| {
+                                o.name("id")
+                                    .description("The job id")
+                                    .required(true)
+                                    .kind(command::CommandOptionType::Integer)
+                            })
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+
+    fn initial_response(
+        &self,
+        _interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        InitialInteractionResponse::DeferEphemeralReply
+    }
+
+    async fn handle_command(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> InteractionResult {
+        let options = &command.data.options[0];
+        let mut resp = CreateInteractionResponseData::default();
+        match options.name.as_str() {
+            "retry" => {
+                let id = options.get_u64("id").unwrap();
+                let now = OffsetDateTime::now_utc();
+                find_job(id).await?;
+                if !DATABASE.run(move |db| db.retry_job(id, now)).await? {
+                    return Err(ErrorResponse::message(format!(
+                        "Job #{} is running right now.",
+                        id
+                    )))?;
+                }
+                tracing::info!("{} retried job {}", command.user.tag(), id);
+                resp.content(format!("Job #{} will run shortly.", id));
+            }
+            "cancel" => {
+                let id = options.get_u64("id").unwrap();
+                if scheduler::is_repeating(&find_job(id).await?.job) {
+                    return Err(ErrorResponse::message(format!(
+                        "Job #{} always runs and can't be cancelled.",
+                        id
+                    )))?;
+                }
+                if !DATABASE.run(move |db| db.cancel_job(id)).await? {
+                    return Err(ErrorResponse::message(format!("There is no job #{}.", id)))?;
+                }
+                tracing::info!("{} cancelled job {}", command.user.tag(), id);
+                resp.content(format!("Cancelled job #{}.", id));
+            }
+            _ => {
+                let jobs = DATABASE.run(|db| db.fetch_jobs()).await?;
+                let failed = jobs.iter().filter(|job| job.failed).count();
+                // Failed jobs need attention, so they go first
+                let mut shown = jobs.iter().collect::<Vec<_>>();
+                shown.sort_by_key(|job| !job.failed);
+                let lines = shown
+                    .iter()
+                    .take(MAX_JOBS)
+                    .map(|job| describe(job))
+                    .collect::<Vec<_>>();
+                resp.embed(|e| {
+                    e.title("Scheduled Jobs")
+                        .description(if lines.is_empty() {
+                            String::from("*None*")
+                        } else {
+                            lines.join("\n")
+                        })
+                        .color(if failed > 0 { 0xFD4659 } else { 0x0E87CC })
+                        .footer(|f| f.text(format!("{} scheduled, {} failed", jobs.len(), failed)))
+                });
+            }
+        }
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
@@ -0,0 +1,298 @@
+use serenity::{
+    async_trait, builder::CreateInteractionResponseData, client::Context,
+    model::application::interaction::application_command::ApplicationCommandInteraction,
+    model::prelude::*,
+};
+use time::OffsetDateTime;
+
+use crate::consts::{CONFIG, DATABASE, MINECRAFT};
+use crate::db::MinecraftAccount;
+use bridge_scrims::interaction::*;
+
+pub struct Link;
+
+/// Whether the name could be a Minecraft account, which are 3 to 16 letters, digits or underscores
+pub fn valid_ign(ign: &str) -> bool {
+    (3..=16).contains(&ign.len()) && ign.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub enum Linked {
+    /// The account was linked under its current name
+    New(String),
+    Already(String),
+    NoSuchAccount,
+}
+
+/// Saves that the user plays on the account. If Mojang can't be reached the name is linked
+/// without its uuid.
+pub async fn link_ign(user: UserId, ign: &str, linked_by: UserId) -> crate::Result<Linked> {
+    if !valid_ign(ign) {
+        return Ok(Linked::NoSuchAccount);
+    }
+    let (ign, uuid) = match MINECRAFT.resolve(ign).await {
+        Ok(Some(profile)) => (profile.name, Some(profile.uuid)),
+        Ok(None) => return Ok(Linked::NoSuchAccount),
+        Err(err) => {
+            tracing::warn!("Failed to resolve Minecraft account {}: {}", ign, err);
+            (ign.to_string(), None)
+        }
+    };
+    let name = ign.clone();
+    let inserted = DATABASE
+        .run(move |db| {
+            db.link_minecraft_account(
+                user.0,
+                &name,
+                uuid.as_deref(),
+                linked_by.0,
+                OffsetDateTime::now_utc(),
+            )
+        })
+        .await?;
+    Ok(if inserted {
+        Linked::New(ign)
+    } else {
+        Linked::Already(ign)
+    })
+}
+
+/// The account's name to show in embeds, marked if the user linked it themselves
+pub fn account_name(account: &MinecraftAccount) -> String {
+    if account.is_verified() {
+        format!("`{}`", account.ign)
+    } else {
+        format!("`{}` (unverified)", account.ign)
+    }
+}
+
+/// The Minecraft accounts of the user ready to be shown, or None if none are known
+pub async fn known_igns(user: UserId) -> Option<String> {
+    let accounts = DATABASE
+        .run(move |db| db.fetch_minecraft_accounts(user.0))
+        .await
+        .map_err(|err| tracing::error!("Failed to fetch Minecraft accounts of {}: {}", user, err))
+        .ok()?;
+    if accounts.is_empty() {
+        return None;
+    }
+    Some(
+        accounts
+            .iter()
+            .map(account_name)
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
+fn is_staff(ctx: &Context, member: &Member) -> bool {
+    member.permissions(ctx).map_or(false, |p| p.administrator())
+        || member
+            .roles
+            .iter()
+            .any(|role| [CONFIG.ss_support, CONFIG.support, CONFIG.trial_support].contains(role))
+}
+
+#[async_trait]
+impl InteractionHandler for Link {
+    fn name(&self) -> String {
+        String::from("link")
+    }
+
+    async fn register(&self, ctx: &Context) -> crate::Result<()> {
+        CONFIG
+            .guild
+            .create_application_command(&ctx, |c| {
+                c.name(self.name())
+                    .description("Links Minecraft accounts to Discord users")
+                    .create_option(|add| {
+                        add.kind(command::CommandOptionType::SubCommand)
+                            .name("add")
+                            .description("Links a Minecraft account")
+                            .create_sub_option(|o| {
+                                o.name("ign")
+                                    .description("The Minecraft in-game name")
+                                    .required(true)
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("user")
+                                    .description(
+                                        "Who plays on the account (staff only) [Default: you]",
+                                    )
+                                    .kind(command::CommandOptionType::User)
+                            })
+                    })
+                    .create_option(|remove| {
+                        remove
+                            .kind(command::CommandOptionType::SubCommand)
+                            .name("remove")
+                            .description("Unlinks a Minecraft account")
+                            .create_sub_option(|o| {
+                                o.name("ign")
+                                    .description("The Minecraft in-game name")
+                                    .required(true)
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("user")
+                                    .description(
+                                        "Who the account is linked to (staff only) [Default: you]",
+                                    )
+                                    .kind(command::CommandOptionType::User)
+                            })
+                    })
+                    .create_option(|list| {
+                        list.kind(command::CommandOptionType::SubCommand)
+                            .name("list")
+                            .description("Shows the Minecraft accounts linked to a user")
+                            .create_sub_option(|o| {
+                                o.name("user")
+                                    .description("The user to show the accounts of [Default: you]")
+                                    .kind(command::CommandOptionType::User)
+                            })
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+
+    fn initial_response(
+        &self,
+        _interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        InitialInteractionResponse::DeferEphemeralReply
+    }
+
+    async fn handle_command(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> InteractionResult {
+        let options = &command.data.options[0];
+        let member = command.member.as_ref().unwrap();
+        let staff = is_staff(ctx, member);
+        let user = match options.get_str("user") {
+            Some(user) => UserId(user.parse()?),
+            None => command.user.id,
+        };
+        // Anyone can manage their own accounts, only staff can manage others
+        if options.name != "list" && user != command.user.id && !staff {
+            return Err(self.no_permissions_error())?;
+        }
+
+        let mut resp = CreateInteractionResponseData::default();
+        match options.name.as_str() {
+            "add" => {
+                let ign = options.get_str("ign").unwrap();
+                if !valid_ign(&ign) {
+                    return Err(ErrorResponse::with_title(
+                        "Invalid Name",
+                        "Minecraft names are 3 to 16 letters, numbers or underscores.",
+                    ))?;
+                }
+                let ign = match link_ign(user, &ign, command.user.id).await? {
+                    Linked::New(ign) => ign,
+                    Linked::Already(ign) => {
+                        return Err(ErrorResponse::message(format!(
+                            "`{}` is already linked to {}.",
+                            ign,
+                            user.mention()
+                        )))?;
+                    }
+                    Linked::NoSuchAccount => {
+                        return Err(ErrorResponse::message(format!(
+                            "There is no Minecraft account called `{}`.",
+                            ign
+                        )))?;
+                    }
+                };
+                tracing::info!("{} linked {} to {}", command.user.tag(), ign, user);
+                resp.content(format!("Linked `{}` to {}.", ign, user.mention()));
+            }
+            "remove" => {
+                let ign = options.get_str("ign").unwrap();
+                let name = ign.clone();
+                if !DATABASE
+                    .run(move |db| db.unlink_minecraft_account(user.0, &name))
+                    .await?
+                {
+                    return Err(ErrorResponse::message(format!(
+                        "`{}` isn't linked to {}.",
+                        ign,
+                        user.mention()
+                    )))?;
+                }
+                tracing::info!("{} unlinked {} from {}", command.user.tag(), ign, user);
+                resp.content(format!("Unlinked `{}` from {}.", ign, user.mention()));
+            }
+            _ => {
+                let accounts = DATABASE
+                    .run(move |db| db.fetch_minecraft_accounts(user.0))
+                    .await?;
+                let lines = accounts
+                    .iter()
+                    .map(|account| {
+                        format!(
+                            "{}{}, linked <t:{}:R> by {}",
+                            account_name(account),
+                            account
+                                .uuid
+                                .as_ref()
+                                .map_or(String::new(), |uuid| format!(" ({})", uuid)),
+                            account.linked_at.unix_timestamp(),
+                            UserId(account.linked_by).mention()
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                resp.embed(|e| {
+                    e.title("Minecraft Accounts")
+                        .description(format!(
+                            "{}\n{}",
+                            user.mention(),
+                            if lines.is_empty() {
+                                String::from("*None*")
+                            } else {
+                                lines.join("\n")
+                            }
+                        ))
+                        .color(0x0E87CC)
+                });
+            }
+        }
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn igns() {
+        assert!(valid_ign("Notch"));
+        assert!(valid_ign("some_player_1234"));
+        assert!(!valid_ign("ab"));
+        assert!(!valid_ign("some_player_12345"));
+        assert!(!valid_ign("with space"));
+        assert!(!valid_ign("dash-name"));
+        assert!(!valid_ign("ünicode"));
+    }
+
+    #[test]
+    fn self_links_are_unverified() {
+        let mut account = MinecraftAccount {
+            user: 1,
+            ign: String::from("Notch"),
+            uuid: None,
+            linked_by: 1,
+            linked_at: OffsetDateTime::now_utc(),
+        };
+        assert_eq!(account_name(&account), "`Notch` (unverified)");
+        account.linked_by = 2;
+        assert_eq!(account_name(&account), "`Notch`");
+    }
+}
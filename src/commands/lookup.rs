@@ -0,0 +1,297 @@
+use std::collections::HashSet;
+
+use serenity::{
+    async_trait, builder::CreateInteractionResponseData, client::Context,
+    model::application::interaction::application_command::ApplicationCommandInteraction,
+    model::prelude::*,
+};
+
+use crate::consts::{CONFIG, DATABASE, MINECRAFT};
+use crate::db::{AsyncDatabase, MinecraftAccount, Note, ScrimBan};
+use bridge_scrims::{
+    interaction::*,
+    minecraft::{Profile, ProfileResolver},
+};
+
+/// Most users shown for one account, every user is an embed field
+const MAX_USERS: usize = 10;
+/// Discord rejects embeds over 6000 characters, this leaves room for the title and footer
+const MAX_EMBED_LENGTH: usize = 5500;
+/// Newest notes shown per user
+const MAX_NOTES: usize = 3;
+
+pub struct Lookup;
+
+/// What the looked up name belongs to
+pub enum Account {
+    Found(Profile),
+    /// No account has the name (anymore)
+    Missing,
+    /// Mojang couldn't be asked, so it is unknown whether the name still has an account
+    Unreachable,
+}
+
+/// A Discord user that plays on the account that was looked up
+pub struct LinkedUser {
+    pub user: u64,
+    /// Every account the user has linked, not just the one that was looked up
+    pub accounts: Vec<MinecraftAccount>,
+    pub ban: Option<ScrimBan>,
+    pub notes: Vec<Note>,
+}
+
+/// Finds everyone linked to the account by its uuid, or only by name if it can't be resolved.
+pub async fn lookup(
+    resolver: &dyn ProfileResolver,
+    db: &AsyncDatabase,
+    ign: &str,
+) -> crate::Result<(Account, Vec<LinkedUser>)> {
+    if !super::link::valid_ign(ign) {
+        return Err(ErrorResponse::message(format!(
+            "`{}` isn't a valid Minecraft name.",
+            ign
+        )))?;
+    }
+    let account = match resolver.resolve(ign).await {
+        Ok(Some(profile)) => Account::Found(profile),
+        Ok(None) => Account::Missing,
+        Err(err) => {
+            tracing::warn!("Failed to resolve Minecraft account {}: {}", ign, err);
+            Account::Unreachable
+        }
+    };
+
+    let uuid = match &account {
+        Account::Found(profile) => Some(profile.uuid.clone()),
+        _ => None,
+    };
+    let ign = ign.to_string();
+    let users = db
+        .run(move |db| {
+            let mut seen = HashSet::new();
+            db.fetch_minecraft_account_users(&ign, uuid.as_deref())?
+                .into_iter()
+                .map(|account| account.user)
+                .filter(|user| seen.insert(*user))
+                .map(|user| {
+                    let ban = db
+                        .fetch_scrim_ban_history(user)?
+                        .into_iter()
+                        .find(|ban| ban.is_active());
+                    Ok(LinkedUser {
+                        user,
+                        accounts: db.fetch_minecraft_accounts(user)?,
+                        ban,
+                        notes: db.fetch_notes_for(user)?,
+                    })
+                })
+                .collect::<crate::db::Result<Vec<_>>>()
+        })
+        .await?;
+    Ok((account, users))
+}
+
+fn describe(linked: &LinkedUser) -> String {
+    let mut lines = vec![
+        UserId(linked.user).mention().to_string(),
+        format!(
+            "**Minecraft:** {}",
+            linked
+                .accounts
+                .iter()
+                .map(super::link::account_name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    ];
+    lines.push(match &linked.ban {
+        Some(ban) => format!(
+            "**Banned:** until {} (#{}) ```{}```",
+            super::screenshare::ban::expiry(ban.expires_at),
+            ban.id,
+            ban.reason
+        ),
+        None => String::from("**Banned:** No"),
+    });
+    if !linked.notes.is_empty() {
+        lines.push(format!("**Notes:** {}", linked.notes.len()));
+        for note in linked.notes.iter().rev().take(MAX_NOTES) {
+            lines.push(format!(
+                "• <t:{}:d> `{}` by {}",
+                note.created_at.unix_timestamp(),
+                note.note,
+                UserId(note.creator).mention()
+            ));
+        }
+    }
+    let text = lines.join("\n");
+    // Embed fields fit 1024 characters
+    if text.chars().count() > 1024 {
+        return text.chars().take(1021).chain("...".chars()).collect();
+    }
+    text
+}
+
+#[async_trait]
+impl InteractionHandler for Lookup {
+    fn name(&self) -> String {
+        String::from("lookup")
+    }
+
+    fn allowed_roles(&self) -> Option<Vec<RoleId>> {
+        Some(vec![
+            crate::CONFIG.ss_support,
+            crate::CONFIG.support,
+            crate::CONFIG.trial_support,
+        ])
+    }
+
+    async fn register(&self, ctx: &Context) -> crate::Result<()> {
+        CONFIG
+            .guild
+            .create_application_command(&ctx, |c| {
+                c.name(self.name())
+                    .description("Shows the users, bans and notes of a Minecraft account")
+                    .default_member_permissions(Permissions::empty())
+                    .create_option(|o| {
+                        o.name("ign")
+                            .description("The Minecraft in-game name")
+                            .required(true)
+                            .kind(command::CommandOptionType::String)
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+
+    fn initial_response(
+        &self,
+        _interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        InitialInteractionResponse::DeferEphemeralReply
+    }
+
+    async fn handle_command(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> InteractionResult {
+        let ign = command.get_str("ign").unwrap();
+        let (account, users) = lookup(MINECRAFT.as_ref(), &DATABASE, &ign).await?;
+        let (name, description) = match account {
+            Account::Found(profile) => (profile.name, format!("**UUID:** `{}`", profile.uuid)),
+            Account::Missing => (
+                ign,
+                String::from("*Not a Minecraft account anymore, matched by name only*"),
+            ),
+            Account::Unreachable => (
+                ign,
+                String::from("*Mojang could not be reached, matched by name only*"),
+            ),
+        };
+        if users.is_empty() {
+            return Err(ErrorResponse::message(format!(
+                "`{}` isn't linked to anyone.",
+                name
+            )))?;
+        }
+
+        let title = format!("Lookup: {}", name);
+        let mut length = title.chars().count() + description.chars().count();
+        let mut shown = 0;
+        let mut resp = CreateInteractionResponseData::default();
+        resp.embed(|e| {
+            e.title(title).description(description).color(
+                if users.iter().any(|user| user.ban.is_some()) {
+                    0xFD4659
+                } else {
+                    0x0E87CC
+                },
+            );
+            for user in users.iter().take(MAX_USERS) {
+                let name = format!("Account #{}", shown + 1);
+                let value = describe(user);
+                length += name.chars().count() + value.chars().count();
+                if length > MAX_EMBED_LENGTH {
+                    break;
+                }
+                e.field(name, value, false);
+                shown += 1;
+            }
+            if users.len() > shown {
+                e.footer(|f| f.text(format!("...and {} more", users.len() - shown)));
+            }
+            e
+        });
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::db::{Database, SqliteDatabase};
+    use bridge_scrims::minecraft::StubResolver;
+
+    struct Offline;
+
+    #[async_trait]
+    impl ProfileResolver for Offline {
+        async fn resolve(&self, _ign: &str) -> bridge_scrims::Result<Option<Profile>> {
+            Err("Mojang is down".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn finds_renamed_accounts() {
+        let sqlite = SqliteDatabase::open(":memory:").unwrap();
+        let now = OffsetDateTime::now_utc();
+        // linked before the account was renamed to Cheater
+        sqlite
+            .link_minecraft_account(1, "OldName", Some("uuid1"), 1, now)
+            .unwrap();
+        sqlite
+            .link_minecraft_account(2, "Cheater", None, 2, now)
+            .unwrap();
+        sqlite
+            .link_minecraft_account(2, "Second", None, 2, now)
+            .unwrap();
+        sqlite
+            .link_minecraft_account(3, "Someone", Some("uuid3"), 3, now)
+            .unwrap();
+        sqlite.add_scrim_ban(1, 9, "Cheating", now, None).unwrap();
+        sqlite.add_note(2, now, "Suspicious", 9).unwrap();
+        let db = AsyncDatabase::new(Arc::new(sqlite));
+
+        let resolver = StubResolver::new().with("Cheater", "uuid1");
+        let (account, users) = lookup(&resolver, &db, "cheater").await.unwrap();
+        assert!(matches!(account, Account::Found(profile) if profile.name == "Cheater"));
+        assert_eq!(users.iter().map(|u| u.user).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(users[0].ban.as_ref().unwrap().reason, "Cheating");
+        assert!(users[0].notes.is_empty());
+        assert!(users[1].ban.is_none());
+        assert_eq!(users[1].notes[0].note, "Suspicious");
+        assert_eq!(users[1].accounts.len(), 2);
+        assert!(describe(&users[0]).contains("Cheating"));
+
+        // names that no account has anymore are still matched
+        let (account, users) = lookup(&resolver, &db, "OldName").await.unwrap();
+        assert!(matches!(account, Account::Missing));
+        assert_eq!(users.len(), 1);
+
+        // an outage isn't mistaken for a missing account
+        let (account, users) = lookup(&Offline, &db, "Cheater").await.unwrap();
+        assert!(matches!(account, Account::Unreachable));
+        assert_eq!(users.iter().map(|u| u.user).collect::<Vec<_>>(), vec![2]);
+
+        assert!(lookup(&resolver, &db, "../Cheater").await.is_err());
+    }
+}
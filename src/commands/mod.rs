@@ -1,11 +1,18 @@
 pub mod screenshare {
+    pub mod admit;
+    pub mod appeal;
     pub mod ban;
+    pub mod ban_history;
+    pub mod bulk;
     pub mod close;
+    pub mod evasion;
     pub mod freeze;
     pub mod list_bans;
+    pub mod queue;
     #[allow(clippy::module_inception)]
     pub mod screenshare;
     pub mod screensharers;
+    pub mod stats;
     pub mod ticket;
     pub mod unban;
     pub mod unfreeze;
@@ -13,6 +20,9 @@ pub mod screenshare {
 
 pub mod captains;
 pub mod council;
+pub mod jobs;
+pub mod link;
+pub mod lookup;
 pub mod notes;
 pub mod ping;
 pub mod prefabs;
@@ -94,7 +94,12 @@ impl InteractionHandler for Notes {
                     .to_user(&ctx.http)
                     .await?;
                 let user_id = user.id;
-                let notes = crate::consts::DATABASE.fetch_notes_for(user_id.0);
+                let notes = crate::consts::DATABASE
+                    .run(move |db| db.fetch_notes_for(user_id.0))
+                    .await?;
+                let igns = crate::commands::link::known_igns(user_id)
+                    .await
+                    .map_or(String::new(), |igns| format!("\n**Minecraft:** {}", igns));
 
                 if notes.is_empty() {
                     command
@@ -102,8 +107,8 @@ impl InteractionHandler for Notes {
                             r.embed(|e| {
                                 e.title("No notes found!")
                                     .description(format!(
-                                        "<@{}> currently has no notes.",
-                                        user_id.0
+                                        "<@{}> currently has no notes.{}",
+                                        user_id.0, igns
                                     ))
                                     .color(Color::BLURPLE)
                             })
@@ -122,8 +127,8 @@ impl InteractionHandler for Notes {
                                         (notes.len() / 10) + 1
                                     ))
                                     .description(format!(
-                                        "<@{}> currently the following notes:",
-                                        user_id.0
+                                        "<@{}> currently the following notes:{}",
+                                        user_id.0, igns
                                     ))
                                     .color(Color::BLURPLE);
                                     for note in chunk {
@@ -180,8 +185,10 @@ impl InteractionHandler for Notes {
                 let now = OffsetDateTime::now_utc();
                 let note = cmd.get_str("note").unwrap();
 
-                let noteid =
-                    crate::consts::DATABASE.add_note(user_id.0, now, &note, command.user.id.0)?;
+                let (text, creator) = (note.clone(), command.user.id.0);
+                let noteid = crate::consts::DATABASE
+                    .run(move |db| db.add_note(user_id.0, now, &text, creator))
+                    .await?;
                 if noteid == -1 {
                     command
                         .edit_original_interaction_response(&ctx, |r| {
@@ -216,7 +223,9 @@ impl InteractionHandler for Notes {
 
                 let noteid = cmd.get_u64("noteid").unwrap();
 
-                crate::consts::DATABASE.remove_note(user_id.0, noteid)?;
+                crate::consts::DATABASE
+                    .run(move |db| db.remove_note(user_id.0, noteid))
+                    .await?;
 
                 command
                     .edit_original_interaction_response(&ctx, |r| {
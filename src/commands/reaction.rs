@@ -19,7 +19,7 @@ pub struct ListReactions;
 #[derive(Debug)]
 pub struct ReactionError {
     kind: ErrorKind,
-    db_error: Option<sqlite::Error>,
+    db_error: Option<crate::db::Error>,
 }
 
 impl Display for ReactionError {
@@ -99,7 +99,10 @@ impl InteractionHandler for DelReaction {
             .await?;
         let user_id = user.id;
 
-        if let Err(db_error) = crate::consts::DATABASE.remove_custom_reaction(user_id.0) {
+        if let Err(db_error) = crate::consts::DATABASE
+            .run(move |db| db.remove_custom_reaction(user_id.0))
+            .await
+        {
             command
                 .edit_original_interaction_response(&ctx, |r| {
                     r.embed(|e| {
@@ -231,8 +234,10 @@ impl InteractionHandler for Reaction {
                     }));
                 }
 
-                let reactions_with_trigger =
-                    crate::consts::DATABASE.fetch_custom_reactions_with_trigger(&trigger)?;
+                let query = trigger.clone();
+                let reactions_with_trigger = crate::consts::DATABASE
+                    .run(move |db| db.fetch_custom_reactions_with_trigger(&query))
+                    .await?;
 
                 if !reactions_with_trigger.is_empty() {
                     command
@@ -279,8 +284,10 @@ impl InteractionHandler for Reaction {
                         db_error: None,
                     }));
                 }
-                let user_reactions =
-                    crate::consts::DATABASE.fetch_custom_reactions_for(command.user.id.0);
+                let user = command.user.id.0;
+                let user_reactions = crate::consts::DATABASE
+                    .run(move |db| db.fetch_custom_reactions_for(user))
+                    .await?;
 
                 if !user_reactions.is_empty() {
                     command
@@ -298,8 +305,10 @@ impl InteractionHandler for Reaction {
                     }));
                 }
                 // put it in the db, if there is an error let the user know that it didn't work
-                if let Err(db_error) =
-                    crate::consts::DATABASE.add_custom_reaction(command.user.id.0, &emoji, &trigger)
+                let (new_emoji, new_trigger) = (emoji.clone(), trigger.clone());
+                if let Err(db_error) = crate::consts::DATABASE
+                    .run(move |db| db.add_custom_reaction(user, &new_emoji, &new_trigger))
+                    .await
                 {
                     command
                         .edit_original_interaction_response(&ctx, |r| {
@@ -339,8 +348,10 @@ impl InteractionHandler for Reaction {
                 }
             }
             "remove" => {
-                if let Err(db_error) =
-                    crate::consts::DATABASE.remove_custom_reaction(command.user.id.0)
+                let user = command.user.id.0;
+                if let Err(db_error) = crate::consts::DATABASE
+                    .run(move |db| db.remove_custom_reaction(user))
+                    .await
                 {
                     command
                         .edit_original_interaction_response(&ctx, |r| {
@@ -423,7 +434,9 @@ impl InteractionHandler for ListReactions {
                     .kind(interaction::InteractionResponseType::DeferredChannelMessageWithSource)
             })
             .await?;
-        let reactions = crate::consts::DATABASE.fetch_custom_reactions();
+        let reactions = crate::consts::DATABASE
+            .run(|db| db.fetch_custom_reactions())
+            .await?;
 
         if reactions.is_empty() {
             command
@@ -0,0 +1,181 @@
+use serenity::{
+    async_trait, builder::CreateInteractionResponseData, client::Context,
+    model::application::interaction::message_component::MessageComponentInteraction,
+    model::prelude::*,
+};
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+
+use super::ban::{expiry, scrim_ban, unban_date_after};
+use super::stats::record_action;
+use super::unfreeze::{add_screensharer, unfreeze_user};
+use crate::consts::{CONFIG, DATABASE};
+use crate::db::ScreenshareActionKind;
+use crate::features::frozen::{refreeze, text_channel};
+use bridge_scrims::interaction::*;
+
+lazy_static::lazy_static! {
+    /// Held while an admission is confirmed, so it can't be confirmed twice at once
+    static ref CONFIRMING: Mutex<()> = Mutex::new(());
+}
+
+/// The "I admit" button of the freeze message, only usable by the frozen user
+pub struct Admit;
+/// Lets screensharers give a user that admitted the reduced ban
+pub struct ConfirmAdmission;
+
+#[async_trait]
+impl InteractionHandler for Admit {
+    fn name(&self) -> String {
+        String::from("admit")
+    }
+
+    fn initial_response(
+        &self,
+        _interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        InitialInteractionResponse::DeferEphemeralReply
+    }
+
+    async fn handle_component(
+        &self,
+        ctx: &Context,
+        command: &MessageComponentInteraction,
+        args: &[&str],
+    ) -> InteractionResult {
+        let user = UserId(args.first().unwrap().parse()?);
+        if command.user.id != user {
+            return Err(ErrorResponse::message(format!(
+                "Only {} can admit here.",
+                user.mention()
+            )))?;
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let freeze = DATABASE
+            .run(move |db| match db.admit_freeze(user.0, now)? {
+                true => db.fetch_freezes_for(user.0),
+                false => Ok(None),
+            })
+            .await?
+            .ok_or_else(|| {
+                ErrorResponse::message("You are either not frozen or already admitted.")
+            })?;
+        tracing::info!("{} admitted to cheating", command.user.tag());
+
+        text_channel(&freeze)
+            .send_message(&ctx, |msg| {
+                msg.content(format!(
+                    "{} {} admitted to cheating. Confirming bans them until {}.",
+                    CONFIG.ss_support.mention(),
+                    user.mention(),
+                    expiry(Some(unban_date_after(CONFIG.admission_ban.0)))
+                ))
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|b| {
+                            b.custom_id(format!("confirmadmission:{}", user))
+                                .label("Confirm Ban")
+                                .style(component::ButtonStyle::Danger)
+                        })
+                    })
+                })
+            })
+            .await?;
+
+        let mut resp = CreateInteractionResponseData::default();
+        resp.content("Your admission was recorded, a screensharer will confirm your ban shortly.");
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+#[async_trait]
+impl InteractionHandler for ConfirmAdmission {
+    fn name(&self) -> String {
+        String::from("confirmadmission")
+    }
+
+    fn allowed_roles(&self) -> Option<Vec<RoleId>> {
+        Some(vec![CONFIG.ss_support])
+    }
+
+    fn initial_response(
+        &self,
+        _interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        InitialInteractionResponse::DeferReply
+    }
+
+    async fn handle_component(
+        &self,
+        ctx: &Context,
+        command: &MessageComponentInteraction,
+        args: &[&str],
+    ) -> InteractionResult {
+        let user = UserId(args.first().unwrap().parse()?);
+        let staff = command.user.id;
+        let _confirming = CONFIRMING.lock().await;
+
+        let freeze = DATABASE
+            .run(move |db| db.fetch_freezes_for(user.0))
+            .await?
+            .filter(|freeze| freeze.admitted_at.is_some())
+            .ok_or_else(|| {
+                ErrorResponse::message(format!(
+                    "{} is not frozen or hasn't admitted.",
+                    user.mention()
+                ))
+            })?;
+
+        unfreeze_user(ctx, user).await?;
+        let ban = scrim_ban(
+            ctx,
+            user,
+            staff,
+            Some(unban_date_after(CONFIG.admission_ban.0)),
+            String::from("Admitted to cheating"),
+            None,
+        )
+        .await;
+        let embed = match ban {
+            Ok(embed) => embed,
+            Err(err) => {
+                tracing::error!("Failed to ban {} after they admitted: {}", user, err);
+                refreeze(ctx, freeze).await.map_err(|err| {
+                    tracing::error!("Failed to freeze {} again: {}", user, err);
+                    ErrorResponse::with_title(
+                        "Ban Failed",
+                        format!(
+                            "{} was unfrozen but couldn't be banned or frozen again.",
+                            user.mention()
+                        ),
+                    )
+                })?;
+                return Err(ErrorResponse::with_title(
+                    "Ban Failed",
+                    format!("{} couldn't be banned and is still frozen.", user.mention()),
+                ))?;
+            }
+        };
+        add_screensharer(staff).await;
+        record_action(staff, user, ScreenshareActionKind::Unfreeze).await;
+
+        let _ = command
+            .channel_id
+            .edit_message(ctx, command.message.id, |m| m.components(|c| c))
+            .await
+            .map_err(|err| tracing::error!("Failed to remove the confirm button: {}", err));
+
+        let mut resp = CreateInteractionResponseData::default();
+        resp.add_embed(embed);
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
@@ -0,0 +1,529 @@
+use serenity::{
+    async_trait,
+    builder::{CreateComponents, CreateEmbed, CreateInteractionResponseData},
+    client::Context,
+    model::application::interaction::{
+        message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+    },
+    model::prelude::*,
+};
+use time::OffsetDateTime;
+
+use super::ban::expiry;
+use crate::{
+    consts::{CONFIG, DATABASE},
+    db::{self, AppealStatus},
+};
+use bridge_scrims::interaction::*;
+
+const APPEAL_LENGTH: u64 = 1000;
+
+/// The button on ban DMs and the modal it opens, which banned users appeal their ban with
+pub struct Appeal;
+
+/// Accepting or denying an appeal from the review channel
+pub struct AppealReview;
+
+/// Shortening the ban instead, which asks for the new duration in a modal first
+pub struct AppealReduce;
+
+fn staff_roles() -> Option<Vec<RoleId>> {
+    Some(vec![
+        crate::CONFIG.ss_support,
+        crate::CONFIG.support,
+        crate::CONFIG.trial_support,
+    ])
+}
+
+/// Makes sure the ban belongs to the user and can still be appealed
+async fn appealable_ban(user: UserId, ban: u64) -> crate::Result<db::ScrimBan> {
+    let (ban, appeal) = DATABASE
+        .run(move |db| Ok((db.fetch_scrim_ban(ban)?, db.fetch_appeal_for_ban(ban)?)))
+        .await?;
+    let ban = match ban {
+        Some(ban) if ban.user == user.0 => ban,
+        _ => return Err(ErrorResponse::message("This ban could not be found."))?,
+    };
+    if !ban.is_active() {
+        return Err(ErrorResponse::message("This ban is no longer in effect."))?;
+    }
+    if appeal.is_some() {
+        return Err(ErrorResponse::message("You already appealed this ban."))?;
+    }
+    Ok(ban)
+}
+
+async fn pending_appeal(id: u64) -> crate::Result<db::Appeal> {
+    let appeal = DATABASE
+        .run(move |db| db.fetch_appeal(id))
+        .await?
+        .ok_or_else(|| ErrorResponse::message("This appeal could not be found."))?;
+    if appeal.status != AppealStatus::Pending {
+        return Err(ErrorResponse::message(format!(
+            "This appeal was already {}.",
+            appeal.status
+        )))?;
+    }
+    Ok(appeal)
+}
+
+/// Makes sure the ban the appeal is about is the one the user is still serving, so lifting or
+/// shortening their current ban doesn't touch a newer ban
+async fn appealed_ban(appeal: &db::Appeal) -> crate::Result<()> {
+    let ban = appeal.ban;
+    let active = DATABASE
+        .run(move |db| db.fetch_scrim_ban(ban))
+        .await?
+        .map_or(false, |ban| ban.is_active());
+    if !active {
+        return Err(ErrorResponse::message(
+            "The appealed ban is no longer in effect.",
+        ))?;
+    }
+    Ok(())
+}
+
+async fn active_ban(user: u64) -> crate::Result<db::ScrimUnban> {
+    Ok(DATABASE
+        .run(|db| db.fetch_scrim_unbans())
+        .await?
+        .into_iter()
+        .find(|x| x.id == user && !x.is_expired())
+        .ok_or_else(|| {
+            ErrorResponse::message(format!("{} is no longer banned.", UserId(user).mention()))
+        })?)
+}
+
+fn review_embed(user: &User, ban: &db::ScrimBan, appeal_id: i64, appeal: &str) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .author(|a| {
+            a.name(format!("{} Appealed their Scrim Ban", user.tag()))
+                .icon_url(
+                    user.avatar_url()
+                        .unwrap_or_else(|| user.default_avatar_url()),
+                )
+        })
+        .title(format!("Appeal #{}", appeal_id))
+        .field("User", user.mention(), true)
+        .field(
+            "Banned By",
+            ban.staff.map_or(String::from("Unknown"), |id| {
+                UserId(id).mention().to_string()
+            }),
+            true,
+        )
+        .field("Expires", expiry(ban.expires_at), true)
+        .field("Ban Reason", format!("```{}```", ban.reason), false)
+        .field("Appeal", format!("```{}```", appeal), false)
+        .color(0x0E87CC);
+    embed
+}
+
+fn review_buttons(components: &mut CreateComponents, appeal_id: i64) -> &mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|b| {
+            b.custom_id(format!("appealreview:accept:{}", appeal_id))
+                .label("Accept")
+                .style(component::ButtonStyle::Success)
+        })
+        .create_button(|b| {
+            b.custom_id(format!("appealreduce:{}", appeal_id))
+                .label("Reduce")
+                .style(component::ButtonStyle::Primary)
+        })
+        .create_button(|b| {
+            b.custom_id(format!("appealreview:deny:{}", appeal_id))
+                .label("Deny")
+                .style(component::ButtonStyle::Danger)
+        })
+    })
+}
+
+fn outcome(status: AppealStatus) -> (&'static str, u32) {
+    match status {
+        AppealStatus::Pending => ("Pending", 0x0E87CC),
+        AppealStatus::Accepted => ("Accepted", 0x20BF72),
+        AppealStatus::Denied => ("Denied", 0xFD4659),
+        AppealStatus::Reduced => ("Reduced", 0x0E87CC),
+    }
+}
+
+/// Records the decision before it is carried out, so only one staff member decides on the
+/// appeal. Returns the appeal with the decision.
+async fn claim(id: u64, status: AppealStatus, staff: UserId) -> crate::Result<db::Appeal> {
+    let reviewed_at = OffsetDateTime::now_utc();
+    Ok(DATABASE
+        .run(
+            move |db| match db.decide_appeal(id, status, staff.0, reviewed_at)? {
+                true => db.fetch_appeal(id),
+                false => Ok(None),
+            },
+        )
+        .await?
+        .ok_or_else(|| ErrorResponse::message("This appeal was already decided on."))?)
+}
+
+/// Puts the appeal back up for review after the decision on it failed
+async fn reopen(id: u64) {
+    let _ = DATABASE
+        .run(move |db| db.reopen_appeal(id))
+        .await
+        .map_err(|err| tracing::error!("Failed to reopen appeal #{}: {}", id, err));
+}
+
+/// Closes the review message and lets the user know the outcome of a claimed appeal
+async fn announce(
+    ctx: &Context,
+    appeal: &db::Appeal,
+    review: Option<&Message>,
+    details: Option<String>,
+) -> crate::Result<()> {
+    let (title, color) = outcome(appeal.status);
+    let decision = format!(
+        "{} by {}{}",
+        title,
+        appeal.reviewed_by.map_or(String::from("Unknown"), |id| {
+            UserId(id).mention().to_string()
+        }),
+        appeal.reviewed_at.map_or(String::new(), |at| format!(
+            " <t:{}:R>",
+            at.unix_timestamp()
+        ))
+    );
+    if let Some(review) = review {
+        let mut embed = review
+            .embeds
+            .first()
+            .cloned()
+            .map(CreateEmbed::from)
+            .unwrap_or_default();
+        embed
+            .field(
+                "Decision",
+                format!(
+                    "{}{}",
+                    decision,
+                    details
+                        .as_ref()
+                        .map_or(String::new(), |details| format!(", {}", details))
+                ),
+                false,
+            )
+            .color(color);
+        let _ = review
+            .channel_id
+            .edit_message(ctx, review.id, |m| m.set_embed(embed).components(|c| c))
+            .await
+            .map_err(|err| tracing::error!("Failed to close appeal review message: {}", err));
+    }
+
+    let mut dm_embed = CreateEmbed::default();
+    dm_embed
+        .title(format!("Your Scrim Ban Appeal was {}", title))
+        .field(
+            format!("Your Appeal (<t:{}:d>)", appeal.created_at.unix_timestamp()),
+            format!("```{}```", appeal.appeal),
+            false,
+        )
+        .color(color)
+        .footer(|f| {
+            CONFIG
+                .guild
+                .to_guild_cached(ctx)
+                .unwrap()
+                .icon_url()
+                .map(|url| f.icon_url(url));
+            f.text(CONFIG.guild.name(ctx).unwrap())
+        });
+    if let Some(details) = details {
+        dm_embed.description(format!("Your ban {}.", details));
+    }
+    if let Ok(dm) = UserId(appeal.user).create_dm_channel(ctx).await {
+        let _ = dm.send_message(ctx, |msg| msg.set_embed(dm_embed)).await;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl InteractionHandler for Appeal {
+    fn name(&self) -> String {
+        String::from("appeal")
+    }
+
+    // Appeals are made from DMs, where there are no roles to check.
+    // Only the banned user can appeal their ban, which is checked when handling it.
+    async fn verify_execution<'a>(
+        &self,
+        _ctx: &Context,
+        _user: &User,
+        _member: &Option<Member>,
+        _channel: &ChannelId,
+    ) -> std::result::Result<(), Box<ErrorResponse<'a>>> {
+        Ok(())
+    }
+
+    fn initial_response(
+        &self,
+        interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        match interaction_type {
+            interaction::InteractionType::ModalSubmit => {
+                InitialInteractionResponse::DeferEphemeralReply
+            }
+            // The button is answered with the modal
+            _ => InitialInteractionResponse::None,
+        }
+    }
+
+    async fn handle_component(
+        &self,
+        ctx: &Context,
+        command: &MessageComponentInteraction,
+        args: &[&str],
+    ) -> InteractionResult {
+        let ban = args.first().unwrap().parse()?;
+        appealable_ban(command.user.id, ban).await?;
+
+        command
+            .create_interaction_response(&ctx, |r| {
+                r.kind(interaction::InteractionResponseType::Modal)
+                    .interaction_response_data(|d| {
+                        d.custom_id(format!("{}:{}", self.name(), ban))
+                            .title("Scrim Ban Appeal")
+                            .components(|c| {
+                                c.create_action_row(|row| {
+                                    row.create_input_text(|i| {
+                                        i.custom_id("appeal")
+                                            .label("Why should your ban be lifted?")
+                                            .style(component::InputTextStyle::Paragraph)
+                                            .max_length(APPEAL_LENGTH)
+                                            .required(true)
+                                    })
+                                })
+                            })
+                    })
+            })
+            .await?;
+        Ok(None)
+    }
+
+    async fn handle_modal(
+        &self,
+        ctx: &Context,
+        modal: &ModalSubmitInteraction,
+        args: &[&str],
+    ) -> InteractionResult {
+        let ban_id = args.first().unwrap().parse()?;
+        let ban = appealable_ban(modal.user.id, ban_id).await?;
+        let appeal = modal.get_str("appeal").unwrap_or_default();
+
+        let (user, text, created_at) = (modal.user.id.0, appeal.clone(), OffsetDateTime::now_utc());
+        let appeal_id = DATABASE
+            .run(move |db| db.add_appeal(ban_id, user, &text, created_at))
+            .await?;
+
+        CONFIG
+            .appeal_reviews
+            .send_message(&ctx, |msg| {
+                msg.set_embed(review_embed(&modal.user, &ban, appeal_id, &appeal))
+                    .components(|c| review_buttons(c, appeal_id))
+            })
+            .await?;
+
+        if let Some(message) = &modal.message {
+            let _ = modal
+                .channel_id
+                .edit_message(&ctx, message.id, |m| {
+                    m.components(|c| {
+                        c.create_action_row(|row| {
+                            row.create_button(|b| {
+                                b.custom_id(format!("{}:{}", self.name(), ban_id))
+                                    .label("Appealed")
+                                    .style(component::ButtonStyle::Secondary)
+                                    .disabled(true)
+                            })
+                        })
+                    })
+                })
+                .await;
+        }
+
+        let mut resp = CreateInteractionResponseData::default();
+        resp.embed(|e| {
+            e.title("Appeal Submitted")
+                .description("Staff will review your appeal and message you once they decided.")
+                .color(0x0E87CC)
+        });
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+#[async_trait]
+impl InteractionHandler for AppealReview {
+    fn name(&self) -> String {
+        String::from("appealreview")
+    }
+
+    fn allowed_roles(&self) -> Option<Vec<RoleId>> {
+        staff_roles()
+    }
+
+    fn initial_response(
+        &self,
+        _interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        InitialInteractionResponse::DeferEphemeralReply
+    }
+
+    async fn handle_component(
+        &self,
+        ctx: &Context,
+        command: &MessageComponentInteraction,
+        args: &[&str],
+    ) -> InteractionResult {
+        let appeal = pending_appeal(args.get(1).unwrap().parse()?).await?;
+        let staff = command.user.id;
+
+        let (status, unban) = match *args.first().unwrap() {
+            "accept" => {
+                appealed_ban(&appeal).await?;
+                (AppealStatus::Accepted, Some(active_ban(appeal.user).await?))
+            }
+            _ => (AppealStatus::Denied, None),
+        };
+        let appeal = claim(appeal.id, status, staff).await?;
+        if let Some(unban) = unban {
+            let res = super::unban::scrim_unban(
+                ctx,
+                Some(staff),
+                &unban,
+                format!("Appeal #{} accepted", appeal.id),
+            )
+            .await;
+            if let Err(err) = res {
+                reopen(appeal.id).await;
+                return Err(err);
+            }
+        }
+        announce(ctx, &appeal, Some(&command.message), None).await?;
+
+        let (title, color) = outcome(status);
+        let mut resp = CreateInteractionResponseData::default();
+        resp.embed(|e| {
+            e.description(format!(
+                "Appeal #{} was {}.",
+                appeal.id,
+                title.to_lowercase()
+            ))
+            .color(color)
+        });
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+#[async_trait]
+impl InteractionHandler for AppealReduce {
+    fn name(&self) -> String {
+        String::from("appealreduce")
+    }
+
+    fn allowed_roles(&self) -> Option<Vec<RoleId>> {
+        staff_roles()
+    }
+
+    fn initial_response(
+        &self,
+        interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        match interaction_type {
+            interaction::InteractionType::ModalSubmit => {
+                InitialInteractionResponse::DeferEphemeralReply
+            }
+            // The button is answered with the modal
+            _ => InitialInteractionResponse::None,
+        }
+    }
+
+    async fn handle_component(
+        &self,
+        ctx: &Context,
+        command: &MessageComponentInteraction,
+        args: &[&str],
+    ) -> InteractionResult {
+        let appeal = pending_appeal(args.first().unwrap().parse()?).await?;
+
+        command
+            .create_interaction_response(&ctx, |r| {
+                r.kind(interaction::InteractionResponseType::Modal)
+                    .interaction_response_data(|d| {
+                        d.custom_id(format!("{}:{}", self.name(), appeal.id))
+                            .title(format!("Reduce Ban (Appeal #{})", appeal.id))
+                            .components(|c| {
+                                c.create_action_row(|row| {
+                                    row.create_input_text(|i| {
+                                        i.custom_id("duration")
+                                            .label("New ban duration, starting now")
+                                            .placeholder("e.g. 3d 12h")
+                                            .style(component::InputTextStyle::Short)
+                                            .required(true)
+                                    })
+                                })
+                            })
+                    })
+            })
+            .await?;
+        Ok(None)
+    }
+
+    async fn handle_modal(
+        &self,
+        ctx: &Context,
+        modal: &ModalSubmitInteraction,
+        args: &[&str],
+    ) -> InteractionResult {
+        let appeal = pending_appeal(args.first().unwrap().parse()?).await?;
+        let duration = super::ban::parse_duration(modal.get_str("duration").unwrap_or_default())?;
+        let unban_date = super::ban::unban_date_after(duration);
+
+        appealed_ban(&appeal).await?;
+        let unban = active_ban(appeal.user).await?;
+        if let Some(date) = unban.date.filter(|date| unban_date >= *date) {
+            return Err(ErrorResponse::message(format!(
+                "That would not shorten the ban, it already expires <t:{}:R>.",
+                date.unix_timestamp()
+            )))?;
+        }
+        let staff = modal.user.id;
+        let appeal = claim(appeal.id, AppealStatus::Reduced, staff).await?;
+        let res = super::ban::edit_scrim_ban(ctx, &unban, staff, Some(unban_date), None).await;
+        if let Err(err) = res {
+            reopen(appeal.id).await;
+            return Err(err);
+        }
+        let details = format!("now expires <t:{}:R>", unban_date.unix_timestamp());
+        announce(ctx, &appeal, modal.message.as_ref(), Some(details.clone())).await?;
+
+        let mut resp = CreateInteractionResponseData::default();
+        resp.embed(|e| {
+            e.description(format!(
+                "Appeal #{} was reduced, the ban {}.",
+                appeal.id, details
+            ))
+            .color(0x0E87CC)
+        });
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
@@ -6,14 +6,21 @@ use serenity::{
     builder::{CreateEmbed, CreateEmbedAuthor, CreateInteractionResponseData},
     client::Context,
     model::application::interaction::{
-        application_command::ApplicationCommandInteraction, MessageFlags,
+        application_command::{ApplicationCommandInteraction, CommandDataOption},
+        MessageFlags,
     },
     model::prelude::*,
 };
 
-use crate::{consts::CONFIG, db::Ids};
+use crate::{
+    consts::{EscalationTier, CONFIG},
+    db::{self, Ids},
+};
 use bridge_scrims::{interaction::*, parse_durations::Duration as ParsedDuration};
 
+/// Why a ban that was still running got revoked when the user was banned again
+const REPLACED: &str = "Replaced by a new ban";
+
 pub struct ScrimBan;
 
 #[async_trait]
@@ -37,23 +44,63 @@ impl InteractionHandler for ScrimBan {
                 c.name(self.name())
                     .description("Bans a user from playing scrims. (Do not confuse with /ban for server bans)")
                     .default_member_permissions(Permissions::empty())
-                    .create_option(|o| {
-                        o.name("user")
-                            .description("The user to ban")
-                            .required(true)
-                            .kind(command::CommandOptionType::User)
-                    })
-                    .create_option(|o| {
-                        o.name("reason")
-                            .description("Reason for the ban")
-                            .required(true)
-                            .kind(command::CommandOptionType::String)
+                    .create_option(|add| {
+                        add.kind(command::CommandOptionType::SubCommand)
+                            .name("add")
+                            .description("Bans a user from playing scrims")
+                            .create_sub_option(|o| {
+                                o.name("user")
+                                    .description("The user to ban")
+                                    .required(true)
+                                    .kind(command::CommandOptionType::User)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("reason")
+                                    .description("Reason for the ban")
+                                    .required(true)
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("duration")
+                                    .description("The ban duration (e.g. 10s 15m 20h 16w 20months 1y). [Default: based on past bans]")
+                                    .required(false)
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("permanent")
+                                    .description("Ban the user until they are unbanned manually")
+                                    .required(false)
+                                    .kind(command::CommandOptionType::Boolean)
+                            })
                     })
-                    .create_option(|o| {
-                        o.name("duration")
-                            .description("The ban duration (e.g. 10s 15m 20h 16w 20months 1y). [Default: 30d]")
-                            .required(false)
-                            .kind(command::CommandOptionType::String)
+                    .create_option(|edit| {
+                        edit.kind(command::CommandOptionType::SubCommand)
+                            .name("edit")
+                            .description("Changes how long a scrim ban lasts or why it was given")
+                            .create_sub_option(|o| {
+                                o.name("user")
+                                    .description("The banned user")
+                                    .required(true)
+                                    .kind(command::CommandOptionType::User)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("extend")
+                                    .description("How much longer the ban should last (e.g. 3d 12h)")
+                                    .required(false)
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("shorten")
+                                    .description("How much sooner the ban should end (e.g. 3d 12h)")
+                                    .required(false)
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("reason")
+                                    .description("The new reason for the ban")
+                                    .required(false)
+                                    .kind(command::CommandOptionType::String)
+                            })
                     })
             })
             .await?;
@@ -72,45 +119,39 @@ impl InteractionHandler for ScrimBan {
         ctx: &Context,
         command: &ApplicationCommandInteraction,
     ) -> InteractionResult {
+        let options = &command.data.options[0];
+        match options.name.as_str() {
+            "edit" => self.edit(ctx, command, options).await,
+            _ => self.ban(ctx, command, options).await,
+        }
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+impl ScrimBan {
+    async fn ban<'a>(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        options: &CommandDataOption,
+    ) -> InteractionResult<'a> {
         let executor = command.member.as_ref().unwrap();
-        let to_ban = UserId(command.get_str("user").unwrap().parse().unwrap());
-        let reason = command
+        let to_ban = UserId(options.get_str("user").unwrap().parse().unwrap());
+        let reason = options
             .get_str("reason")
             .unwrap_or_else(|| String::from("No reason specified"));
 
-        let duration = command.get_str("duration");
-        let unban_date = get_unban_date(duration)?;
-
-        let member = CONFIG.guild.member(&ctx, to_ban).await.ok();
-        if let Some(ref member) = member {
-            let roles = member.roles(ctx).unwrap_or_default();
-            let cmd_roles = executor.roles(ctx).unwrap_or_default();
-
-            let top_role = roles.iter().max();
-            let cmd_top_role = cmd_roles.iter().max();
-
-            if top_role >= cmd_top_role || member.user.bot {
-                return Err(ErrorResponse::with_title(
-                    "Insufficient Permissions",
-                    format!("You do not have permission to ban {}!", member.mention()),
-                ))?;
-            }
-        }
-
-        if crate::consts::DATABASE
-            .fetch_freezes_for(to_ban.0)
-            .is_some()
-        {
-            super::unfreeze::unfreeze_user(ctx, to_ban)
-                .await
-                .map_err(|err| {
-                    tracing::error!("Unfreeze Failed on {}: {}", to_ban, err);
-                    ErrorResponse::with_title(
-                        "User is Frozen",
-                        format!("Unable to unfreeze {}!", to_ban.mention()),
-                    )
-                })?;
+        let length = BanLength::from_options(
+            options.get_str("duration"),
+            options.get_bool("permanent").unwrap_or(false),
+        )?;
+        let (unban_date, tier) = length.resolve(to_ban).await?;
 
+        check_can_ban(ctx, executor, to_ban).await?;
+        if unfreeze_before_ban(ctx, to_ban).await? {
             let _ = command
                 .create_followup_message(&ctx, |msg| {
                     msg.content(format!(
@@ -122,18 +163,185 @@ impl InteractionHandler for ScrimBan {
                 .await;
         }
 
-        let embed = scrim_ban(ctx, to_ban, executor.user.id, unban_date, reason).await?;
+        let embed = scrim_ban(ctx, to_ban, executor.user.id, unban_date, reason, tier).await?;
         let mut resp = CreateInteractionResponseData::default();
         resp.add_embed(embed);
         Ok(Some(resp))
     }
 
-    fn new() -> Box<Self> {
-        Box::new(Self)
+    async fn edit<'a>(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        options: &CommandDataOption,
+    ) -> InteractionResult<'a> {
+        let user = UserId(options.get_str("user").unwrap().parse()?);
+        // In seconds, negative when the ban is shortened
+        let change = match (options.get_str("extend"), options.get_str("shorten")) {
+            (Some(_), Some(_)) => {
+                return Err(ErrorResponse::with_title(
+                    "Invalid Duration",
+                    "A ban can't be extended and shortened at once.",
+                ))?;
+            }
+            (Some(extend), None) => Some(parse_duration(extend)?.0 as i64),
+            (None, Some(shorten)) => Some(-(parse_duration(shorten)?.0 as i64)),
+            (None, None) => None,
+        };
+        let reason = options.get_str("reason");
+        if change.is_none() && reason.is_none() {
+            return Err(ErrorResponse::message(
+                "Nothing to edit. Extend or shorten the ban, or give it a new reason.",
+            ))?;
+        }
+
+        let unban = crate::consts::DATABASE
+            .run(|db| db.fetch_scrim_unbans())
+            .await?
+            .into_iter()
+            .find(|x| x.id == user.0 && !x.is_expired())
+            .ok_or_else(|| ErrorResponse::message(format!("{} is not banned.", user.mention())))?;
+
+        let unban_date = match change {
+            None => unban.date,
+            Some(change) => {
+                let date = unban.date.ok_or_else(|| {
+                    ErrorResponse::with_title(
+                        "Invalid Duration",
+                        "Permanent bans can't be extended or shortened.",
+                    )
+                })? + time::Duration::seconds(change);
+                if date <= OffsetDateTime::now_utc() {
+                    return Err(ErrorResponse::with_title(
+                        "Invalid Duration",
+                        "That would end the ban, use /scrimunban to unban them instead.",
+                    ))?;
+                }
+                Some(date)
+            }
+        };
+
+        let embed = edit_scrim_ban(ctx, &unban, command.user.id, unban_date, reason).await?;
+        let mut resp = CreateInteractionResponseData::default();
+        resp.add_embed(embed);
+        Ok(Some(resp))
     }
 }
 
-fn parse_duration<'a>(resolvable: String) -> err_resp::Result<'a, ParsedDuration> {
+/// How long a ban should last, as chosen by staff
+#[derive(Clone, Copy)]
+pub enum BanLength {
+    Permanent,
+    For(ParsedDuration),
+    /// Picked from the escalation ladder by how often the user was banned before
+    Escalated,
+}
+
+impl BanLength {
+    pub fn from_options<'a>(
+        duration: Option<String>,
+        permanent: bool,
+    ) -> err_resp::Result<'a, Self> {
+        match (duration, permanent) {
+            (Some(_), true) => Err(ErrorResponse::with_title(
+                "Invalid Duration",
+                "A permanent ban can't have a duration.",
+            )),
+            (None, true) => Ok(Self::Permanent),
+            (Some(duration), false) => Ok(Self::For(parse_duration(duration)?)),
+            (None, false) => Ok(Self::Escalated),
+        }
+    }
+
+    /// When the ban of the user would end, and the escalation tier that was picked if any
+    pub async fn resolve(
+        self,
+        user: UserId,
+    ) -> crate::Result<(Option<OffsetDateTime>, Option<EscalationTier>)> {
+        Ok(match self {
+            Self::Permanent => (None, None),
+            Self::For(duration) => (Some(unban_date_after(duration)), None),
+            Self::Escalated => {
+                let offenses = crate::consts::DATABASE
+                    .run(move |db| {
+                        let history = db.fetch_scrim_ban_history(user.0)?;
+                        let overturned = db
+                            .fetch_appeals_for_user(user.0)?
+                            .into_iter()
+                            .filter(|appeal| appeal.status == db::AppealStatus::Accepted)
+                            .map(|appeal| appeal.ban)
+                            .collect();
+                        Ok(offenses(&history, &overturned))
+                    })
+                    .await?;
+                let tier = CONFIG.ban_escalation.tier_for(offenses + 1);
+                (tier.duration.map(unban_date_after), Some(tier))
+            }
+        })
+    }
+}
+
+/// How many of the bans count towards the escalation ladder. Bans lifted by an accepted appeal
+/// (`overturned`) never happened, and a ban replaced by a new one while it was still running is
+/// counted through the new ban.
+fn offenses(history: &[db::ScrimBan], overturned: &HashSet<u64>) -> usize {
+    history
+        .iter()
+        .filter(|ban| !overturned.contains(&ban.id))
+        .filter(|ban| {
+            let replaced = ban.revoke_reason.as_deref() == Some(REPLACED)
+                && ban.revoked_at.map_or(false, |revoked_at| {
+                    ban.expires_at
+                        .map_or(true, |expires_at| revoked_at < expires_at)
+                });
+            !replaced
+        })
+        .count()
+}
+
+/// Staff can only ban members below their top role, and never bots
+pub async fn check_can_ban(ctx: &Context, executor: &Member, to_ban: UserId) -> crate::Result<()> {
+    let member = CONFIG.guild.member(&ctx, to_ban).await.ok();
+    if let Some(ref member) = member {
+        let roles = member.roles(ctx).unwrap_or_default();
+        let cmd_roles = executor.roles(ctx).unwrap_or_default();
+
+        let top_role = roles.iter().max();
+        let cmd_top_role = cmd_roles.iter().max();
+
+        if top_role >= cmd_top_role || member.user.bot {
+            return Err(ErrorResponse::with_title(
+                "Insufficient Permissions",
+                format!("You do not have permission to ban {}!", member.mention()),
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// A ban replaces a freeze, so frozen users are unfrozen first. Returns whether they were frozen.
+pub async fn unfreeze_before_ban(ctx: &Context, to_ban: UserId) -> crate::Result<bool> {
+    if crate::consts::DATABASE
+        .run(move |db| db.fetch_freezes_for(to_ban.0))
+        .await?
+        .is_none()
+    {
+        return Ok(false);
+    }
+
+    super::unfreeze::unfreeze_user(ctx, to_ban)
+        .await
+        .map_err(|err| {
+            tracing::error!("Unfreeze Failed on {}: {}", to_ban, err);
+            ErrorResponse::with_title(
+                "User is Frozen",
+                format!("Unable to unfreeze {}!", to_ban.mention()),
+            )
+        })?;
+    Ok(true)
+}
+
+pub fn parse_duration<'a>(resolvable: String) -> err_resp::Result<'a, ParsedDuration> {
     let duration = resolvable.parse::<ParsedDuration>();
     if duration.is_err() {
         return Err(ErrorResponse::with_title(
@@ -144,25 +352,30 @@ fn parse_duration<'a>(resolvable: String) -> err_resp::Result<'a, ParsedDuration
     Ok(duration.unwrap())
 }
 
-fn get_unban_date<'a>(duration_option: Option<String>) -> err_resp::Result<'a, OffsetDateTime> {
-    let seconds = match duration_option {
-        Some(resolvable) => parse_duration(resolvable)?.0,
-        None => 30 * 24 * 60 * 60,
-    };
-    let now = OffsetDateTime::now_utc();
-    let duration = Duration::from_secs(seconds);
-    Ok(now + duration)
+pub fn unban_date_after(duration: ParsedDuration) -> OffsetDateTime {
+    OffsetDateTime::now_utc() + Duration::from_secs(duration.0)
+}
+
+pub fn expiry(date: Option<OffsetDateTime>) -> String {
+    date.map_or(String::from("Never"), |date| {
+        format!("<t:{}:R>", date.unix_timestamp())
+    })
 }
 
 pub async fn scrim_ban(
     ctx: &Context,
     to_ban_id: UserId,
     executor_id: UserId,
-    unban_date: OffsetDateTime,
+    // None for a permanent ban
+    unban_date: Option<OffsetDateTime>,
     reason: String,
+    // The escalation tier the duration was picked from, if staff didn't choose one
+    tier: Option<EscalationTier>,
 ) -> crate::Result<CreateEmbed> {
     let to_ban = to_ban_id.to_user(ctx).await?;
-    let bans = crate::consts::DATABASE.fetch_scrim_unbans();
+    let bans = crate::consts::DATABASE
+        .run(|db| db.fetch_scrim_unbans())
+        .await?;
     let existing = bans.iter().find(|x| x.id == to_ban.id.0);
 
     let mut author = CreateEmbedAuthor::default();
@@ -176,20 +389,31 @@ pub async fn scrim_ban(
     fields.push((
         "Expires",
         format!(
-            "{}<t:{}:R>",
+            "{}{}",
             existing.map_or(String::default(), |e| format!(
                 "{} **➔** ",
-                e.date.map_or(String::from("*Expired*"), |d| format!(
-                    "<t:{}:R>",
-                    d.unix_timestamp()
-                ))
+                if e.was_logged() {
+                    String::from("*Expired*")
+                } else {
+                    expiry(e.date)
+                }
             )),
-            unban_date.unix_timestamp()
+            expiry(unban_date)
         ),
         true,
     ));
 
     fields.push(("Staff", executor_id.mention().to_string(), true));
+    if let Some(igns) = crate::commands::link::known_igns(to_ban.id).await {
+        fields.push(("Minecraft", igns, true));
+    }
+    if let Some(tier) = tier {
+        fields.push((
+            "Escalation",
+            format!("Tier {} (offense #{})", tier.tier, tier.offense),
+            true,
+        ));
+    }
     fields.push(("Reason", format!("```{}```", reason), false));
 
     let mut embed = CreateEmbed::default();
@@ -206,7 +430,6 @@ pub async fn scrim_ban(
     let mut dm_embed = CreateEmbed::default();
     dm_embed
         .title("You were banned from queuing Scrims")
-        .field("Appeal Channel", CONFIG.appeal_channel.mention(), true)
         .color(0xFD4659)
         .fields(fields)
         .footer(|f| {
@@ -255,25 +478,217 @@ pub async fn scrim_ban(
             .into_iter()
             .collect::<Vec<_>>();
 
-        crate::consts::DATABASE.modify_scrim_unban(
-            to_ban.id.0,
-            Some(unban_date),
-            &Ids(all_removed),
-        )?;
+        let id = to_ban.id.0;
+        crate::consts::DATABASE
+            .run(move |db| db.modify_scrim_unban(id, unban_date, &Ids(all_removed)))
+            .await?;
     } else {
-        crate::consts::DATABASE.add_scrim_unban(
-            to_ban.id.0,
-            Some(unban_date),
-            &removed_roles.into(),
-        )?;
+        let (id, roles): (_, Ids) = (to_ban.id.0, removed_roles.into());
+        crate::consts::DATABASE
+            .run(move |db| db.add_scrim_unban(id, unban_date, &roles))
+            .await?;
+    }
+
+    // Kept so alts of the user can still be recognized after they left
+    let account = db::KnownAccount {
+        id: to_ban.id.0,
+        name: to_ban.name.clone(),
+        avatar: to_ban.avatar.clone(),
+        invite: None,
+    };
+    let _ = crate::consts::DATABASE
+        .run(move |db| db.record_account(&account))
+        .await
+        .map_err(|err| tracing::error!("Failed to record banned account: {}", err));
+
+    let (id, staff, issued_at) = (to_ban.id.0, executor_id.0, OffsetDateTime::now_utc());
+    let ban_id = crate::consts::DATABASE
+        .run(move |db| {
+            // The new ban replaces whatever ban the user was still serving
+            db.revoke_scrim_bans(id, Some(staff), REPLACED, issued_at)?;
+            db.add_scrim_ban(id, staff, &reason, issued_at, unban_date)
+        })
+        .await
+        .map_err(|err| tracing::error!("Failed to record scrim ban in history: {}", err))
+        .ok();
+    // Appeals are tied to the ban in the history, so without it they go through the appeal channel
+    if ban_id.is_none() {
+        dm_embed.field("Appeal Channel", CONFIG.appeal_channel.mention(), true);
+    }
+
+    let _ = CONFIG
+        .support_bans
+        .send_message(&ctx, |msg| msg.set_embed(embed.clone()))
+        .await
+        .map_err(|err| tracing::error!("Failed to log to #bans: {}", err));
+
+    let _ = to_ban
+        .dm(ctx, |msg| {
+            msg.set_embed(dm_embed);
+            if let Some(ban_id) = ban_id {
+                msg.components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|b| {
+                            b.custom_id(format!("appeal:{}", ban_id))
+                                .label("Appeal")
+                                .style(component::ButtonStyle::Primary)
+                        })
+                    })
+                });
+            }
+            msg
+        })
+        .await;
+    Ok(embed)
+}
+
+/// Moves the expiry of a running ban and/or changes its reason, without touching the roles
+pub async fn edit_scrim_ban(
+    ctx: &Context,
+    unban: &db::ScrimUnban,
+    executor_id: UserId,
+    unban_date: Option<OffsetDateTime>,
+    reason: Option<String>,
+) -> crate::Result<CreateEmbed> {
+    let user = UserId(unban.id).to_user(ctx).await?;
+
+    let (id, roles) = (unban.id, Ids(unban.roles.0.clone()));
+    crate::consts::DATABASE
+        .run(move |db| db.modify_scrim_unban(id, unban_date, &roles))
+        .await?;
+
+    let ban = crate::consts::DATABASE
+        .run(move |db| db.fetch_scrim_ban_history(id))
+        .await?
+        .into_iter()
+        .find(|ban| ban.is_active());
+    let old_reason = ban.as_ref().map(|ban| ban.reason.clone());
+    match ban {
+        Some(ban) => {
+            let (staff, edited_at) = (executor_id.0, OffsetDateTime::now_utc());
+            let new_reason = reason.clone().unwrap_or(ban.reason);
+            let _ = crate::consts::DATABASE
+                .run(move |db| db.edit_scrim_ban(ban.id, staff, edited_at, unban_date, &new_reason))
+                .await
+                .map_err(|err| tracing::error!("Failed to record scrim ban edit: {}", err));
+        }
+        None => tracing::warn!("Edited the scrim ban of {} without one in the history", id),
+    }
+
+    let mut fields = vec![
+        (
+            "Expires",
+            format!("{} **➔** {}", expiry(unban.date), expiry(unban_date)),
+            true,
+        ),
+        ("Staff", executor_id.mention().to_string(), true),
+    ];
+    if let (Some(_), Some(old_reason)) = (&reason, &old_reason) {
+        fields.push(("Previous Reason", format!("```{}```", old_reason), false));
+    }
+    if let Some(reason) = reason.as_ref().or(old_reason.as_ref()) {
+        fields.push(("Reason", format!("```{}```", reason), false));
     }
 
+    let mut embed = CreateEmbed::default();
+    embed
+        .author(|a| {
+            a.name(format!("{} Scrim Ban Updated", user.tag()))
+                .icon_url(
+                    user.avatar_url()
+                        .unwrap_or_else(|| user.default_avatar_url()),
+                )
+        })
+        .field("User", user.mention(), true)
+        .color(0x0E87CC)
+        .fields(fields.clone());
+
+    let mut dm_embed = CreateEmbed::default();
+    dm_embed
+        .title("Your Scrim Ban was Updated")
+        .color(0x0E87CC)
+        .fields(fields)
+        .footer(|f| {
+            CONFIG
+                .guild
+                .to_guild_cached(ctx)
+                .unwrap()
+                .icon_url()
+                .map(|url| f.icon_url(url));
+            f.text(CONFIG.guild.name(ctx).unwrap())
+        });
+
     let _ = CONFIG
         .support_bans
         .send_message(&ctx, |msg| msg.set_embed(embed.clone()))
         .await
         .map_err(|err| tracing::error!("Failed to log to #bans: {}", err));
 
-    let _ = to_ban.dm(ctx, |msg| msg.set_embed(dm_embed)).await;
+    let _ = user.dm(ctx, |msg| msg.set_embed(dm_embed)).await;
     Ok(embed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ban(id: u64, revoke_reason: Option<&str>, expired: bool) -> db::ScrimBan {
+        let issued_at = OffsetDateTime::now_utc() - time::Duration::days(10);
+        let expires_at = issued_at + time::Duration::days(7);
+        db::ScrimBan {
+            id,
+            user: 1,
+            staff: Some(2),
+            reason: String::from("cheating"),
+            issued_at: Some(issued_at),
+            expires_at: Some(expires_at),
+            revoked_by: revoke_reason.map(|_| 2),
+            revoke_reason: revoke_reason.map(String::from),
+            revoked_at: revoke_reason.map(|_| {
+                if expired {
+                    expires_at + time::Duration::days(1)
+                } else {
+                    issued_at + time::Duration::days(1)
+                }
+            }),
+        }
+    }
+
+    #[test]
+    fn offenses_count_served_bans() {
+        let history = [ban(1, None, false), ban(2, Some("Ban Expired"), true)];
+        assert_eq!(offenses(&history, &HashSet::new()), 2);
+        assert_eq!(offenses(&[], &HashSet::new()), 0);
+    }
+
+    #[test]
+    fn offenses_skip_overturned_bans() {
+        let history = [
+            ban(1, Some("Appeal #4 accepted"), false),
+            ban(2, None, false),
+        ];
+        assert_eq!(offenses(&history, &HashSet::from([1])), 1);
+    }
+
+    #[test]
+    fn offenses_skip_replaced_bans() {
+        let history = [ban(2, None, false), ban(1, Some(REPLACED), false)];
+        assert_eq!(offenses(&history, &HashSet::new()), 1);
+        // A ban that had already run out before the next one still counts
+        let history = [ban(2, None, false), ban(1, Some(REPLACED), true)];
+        assert_eq!(offenses(&history, &HashSet::new()), 2);
+    }
+
+    #[test]
+    fn offenses_count_migrated_bans_once() {
+        let migrated = |revoke_reason| db::ScrimBan {
+            staff: None,
+            issued_at: None,
+            reason: String::from("Issued before ban history was recorded"),
+            ..ban(1, revoke_reason, false)
+        };
+        assert_eq!(offenses(&[migrated(None)], &HashSet::new()), 1);
+        let history = [ban(2, None, false), migrated(Some(REPLACED))];
+        assert_eq!(offenses(&history, &HashSet::new()), 1);
+    }
+}
@@ -0,0 +1,233 @@
+use serenity::{
+    async_trait,
+    builder::CreateInteractionResponseData,
+    client::Context,
+    model::application::interaction::{
+        application_command::ApplicationCommandInteraction,
+        message_component::MessageComponentInteraction,
+    },
+    model::prelude::*,
+};
+
+use crate::{
+    consts::{CONFIG, DATABASE},
+    db::{ScrimBan, ScrimBanEdit},
+};
+use bridge_scrims::interaction::*;
+
+const PAGE_SIZE: usize = 5;
+
+pub struct BanHistory;
+
+impl BanHistory {
+    async fn history_page<'a>(
+        &self,
+        user: UserId,
+        page: usize,
+    ) -> crate::Result<CreateInteractionResponseData<'a>> {
+        let history = DATABASE
+            .run(move |db| db.fetch_scrim_ban_history(user.0))
+            .await?;
+        if history.is_empty() {
+            return Err(ErrorResponse::message(format!(
+                "{} has never been scrim banned.",
+                user.mention()
+            )))?;
+        }
+
+        let pages = history.len().div_ceil(PAGE_SIZE);
+        let page = page.min(pages - 1);
+        let total = history.len();
+        let igns = crate::commands::link::known_igns(user).await;
+        let shown = DATABASE
+            .run(move |db| {
+                history
+                    .into_iter()
+                    .skip(page * PAGE_SIZE)
+                    .take(PAGE_SIZE)
+                    .map(|ban| {
+                        let edits = db.fetch_scrim_ban_edits(ban.id)?;
+                        Ok((ban, edits))
+                    })
+                    .collect::<crate::db::Result<Vec<_>>>()
+            })
+            .await?;
+
+        let mut resp = CreateInteractionResponseData::default();
+        resp.embed(|e| {
+            e.title("Scrim Ban History")
+                .description(format!(
+                    "{} has been scrim banned {} time(s).{}",
+                    user.mention(),
+                    total,
+                    igns.as_ref()
+                        .map_or(String::new(), |igns| format!("\n**Minecraft:** {}", igns))
+                ))
+                .color(0xFD4659)
+                .footer(|f| f.text(format!("Page {} of {}", page + 1, pages)));
+            for (ban, edits) in &shown {
+                e.field(
+                    format!("#{} • {}", ban.id, ban_status(ban)),
+                    describe_ban(ban, edits),
+                    false,
+                );
+            }
+            e
+        })
+        .components(|c| {
+            c.create_action_row(|row| {
+                row.create_button(|b| {
+                    b.custom_id(self.page_custom_id(user, page.saturating_sub(1)))
+                        .label("Previous")
+                        .style(component::ButtonStyle::Secondary)
+                        .disabled(page == 0)
+                })
+                .create_button(|b| {
+                    b.custom_id(self.page_custom_id(user, page + 1))
+                        .label("Next")
+                        .style(component::ButtonStyle::Secondary)
+                        .disabled(page + 1 >= pages)
+                })
+            })
+        });
+        Ok(resp)
+    }
+
+    fn page_custom_id(&self, user: UserId, page: usize) -> String {
+        format!("{}:{}:{}", self.name(), user, page)
+    }
+}
+
+fn ban_status(ban: &ScrimBan) -> &'static str {
+    if ban.is_active() {
+        "Active"
+    } else if ban.revoked_by.is_some() {
+        "Revoked"
+    } else {
+        "Expired"
+    }
+}
+
+fn timestamp(date: Option<time::OffsetDateTime>, default: &str) -> String {
+    date.map_or(default.to_string(), |date| {
+        format!("<t:{}:f>", date.unix_timestamp())
+    })
+}
+
+fn describe_ban(ban: &ScrimBan, edits: &[ScrimBanEdit]) -> String {
+    let mut lines = vec![
+        format!(
+            "**Staff:** {}",
+            ban.staff.map_or(String::from("Unknown"), |id| UserId(id)
+                .mention()
+                .to_string())
+        ),
+        format!("**Issued:** {}", timestamp(ban.issued_at, "Unknown")),
+        format!("**Expires:** {}", timestamp(ban.expires_at, "Never")),
+    ];
+    if ban.revoked_at.is_some() {
+        lines.push(format!(
+            "**Lifted:** {}{}",
+            timestamp(ban.revoked_at, "Unknown"),
+            ban.revoked_by
+                .map_or(String::new(), |id| format!(" by {}", UserId(id).mention()))
+        ));
+    }
+    if let Some(reason) = &ban.revoke_reason {
+        lines.push(format!("**Lifted Because:** {}", reason));
+    }
+    for edit in edits {
+        let mut changes = Vec::new();
+        if edit.old_expires_at != edit.new_expires_at {
+            changes.push(format!(
+                "expiry {} ➔ {}",
+                timestamp(edit.old_expires_at, "Never"),
+                timestamp(edit.new_expires_at, "Never")
+            ));
+        }
+        if edit.old_reason != edit.new_reason {
+            changes.push(format!("reason was `{}`", edit.old_reason));
+        }
+        lines.push(format!(
+            "**Edited:** {} by {}{}",
+            timestamp(Some(edit.edited_at), "Unknown"),
+            UserId(edit.staff).mention(),
+            if changes.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", changes.join(", "))
+            }
+        ));
+    }
+    lines.push(format!("**Reason:** ```{}```", ban.reason));
+    lines.join("\n")
+}
+
+#[async_trait]
+impl InteractionHandler for BanHistory {
+    fn name(&self) -> String {
+        String::from("banhistory")
+    }
+
+    fn allowed_roles(&self) -> Option<Vec<RoleId>> {
+        Some(vec![
+            crate::CONFIG.ss_support,
+            crate::CONFIG.support,
+            crate::CONFIG.trial_support,
+        ])
+    }
+
+    async fn register(&self, ctx: &Context) -> crate::Result<()> {
+        CONFIG
+            .guild
+            .create_application_command(&ctx, |c| {
+                c.name(self.name())
+                    .description("Shows every scrim ban a user has received")
+                    .default_member_permissions(Permissions::empty())
+                    .create_option(|o| {
+                        o.name("user")
+                            .description("The user to show the ban history of")
+                            .required(true)
+                            .kind(command::CommandOptionType::User)
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+
+    fn initial_response(
+        &self,
+        interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        match interaction_type {
+            interaction::InteractionType::MessageComponent => {
+                InitialInteractionResponse::DeferUpdate
+            }
+            _ => InitialInteractionResponse::DeferEphemeralReply,
+        }
+    }
+
+    async fn handle_command(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> InteractionResult {
+        let user = UserId(command.get_str("user").unwrap().parse()?);
+        Ok(Some(self.history_page(user, 0).await?))
+    }
+
+    async fn handle_component(
+        &self,
+        _ctx: &Context,
+        _command: &MessageComponentInteraction,
+        args: &[&str],
+    ) -> InteractionResult {
+        let user = UserId(args.first().unwrap().parse()?);
+        let page = args.get(1).unwrap().parse()?;
+        Ok(Some(self.history_page(user, page).await?))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
@@ -0,0 +1,341 @@
+use std::time::Duration;
+
+use serenity::{
+    async_trait,
+    builder::CreateInteractionResponseData,
+    client::Context,
+    model::application::interaction::application_command::{
+        ApplicationCommandInteraction, CommandDataOption,
+    },
+    model::prelude::*,
+};
+
+use super::ban::{check_can_ban, scrim_ban, unfreeze_before_ban, BanLength};
+use super::unban::scrim_unban;
+use crate::consts::{CONFIG, DATABASE};
+use bridge_scrims::interaction::*;
+
+/// Most users that can be handled at once, so a single command can't run for too long
+const MAX_USERS: usize = 100;
+/// Largest ID file that is read
+const MAX_FILE_SIZE: u64 = 64 * 1024;
+/// Every ban or unban makes several requests, so the next user waits a bit to stay clear of
+/// Discord's rate limits
+const PACING: Duration = Duration::from_secs(1);
+/// How often the progress message is updated
+const PROGRESS_EVERY: usize = 5;
+
+pub struct BulkScrimBan;
+
+/// Finds every user id in free text, as mentions or raw ids separated by anything.
+/// Returns the ids in order without duplicates, and every part that wasn't an id.
+pub fn parse_user_ids(text: &str) -> (Vec<UserId>, Vec<String>) {
+    let mut ids = Vec::new();
+    let mut invalid = Vec::new();
+    for part in text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .filter(|part| !part.is_empty())
+    {
+        let id = part
+            .trim_start_matches("<@")
+            .trim_start_matches('!')
+            .trim_end_matches('>');
+        match id.parse::<u64>() {
+            Ok(id) if id != 0 => {
+                if !ids.contains(&UserId(id)) {
+                    ids.push(UserId(id));
+                }
+            }
+            _ => invalid.push(part.to_string()),
+        }
+    }
+    (ids, invalid)
+}
+
+/// Joins lines into an embed field value, leaving out whatever doesn't fit
+fn field_list(lines: &[String]) -> String {
+    let mut value = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if value.len() + line.len() > 950 {
+            value.push_str(&format!("*...and {} more*", lines.len() - i));
+            break;
+        }
+        value.push_str(line);
+        value.push('\n');
+    }
+    value
+}
+
+fn error_text(err: &(dyn std::error::Error + Send + Sync + 'static)) -> String {
+    match err.downcast_ref::<Box<ErrorResponse>>() {
+        Some(resp) => resp.1.clone(),
+        None => err.to_string(),
+    }
+}
+
+impl BulkScrimBan {
+    /// Collects the users from the `users` option and the attached ID file
+    async fn users(
+        &self,
+        command: &ApplicationCommandInteraction,
+        options: &CommandDataOption,
+    ) -> crate::Result<(Vec<UserId>, Vec<String>)> {
+        let mut text = options.get_str("users").unwrap_or_default();
+        if let Some(file) = options.get_str("file") {
+            let attachment = file
+                .parse()
+                .ok()
+                .and_then(|id| command.data.resolved.attachments.get(&AttachmentId(id)))
+                .ok_or_else(|| ErrorResponse::message("The attached file could not be found."))?;
+            if attachment.size > MAX_FILE_SIZE {
+                return Err(ErrorResponse::message(format!(
+                    "The attached file is too big, it can be at most {}KB.",
+                    MAX_FILE_SIZE / 1024
+                )))?;
+            }
+            let contents = String::from_utf8(attachment.download().await?).map_err(|_| {
+                ErrorResponse::message("The attached file has to be a text file of user IDs.")
+            })?;
+            text.push('\n');
+            text.push_str(&contents);
+        }
+
+        let (users, invalid) = parse_user_ids(&text);
+        if users.is_empty() {
+            return Err(ErrorResponse::message(
+                "No users were given. Mention them, paste their IDs or attach a file of IDs.",
+            ))?;
+        }
+        if users.len() > MAX_USERS {
+            return Err(ErrorResponse::message(format!(
+                "At most {} users can be handled at once, {} were given.",
+                MAX_USERS,
+                users.len()
+            )))?;
+        }
+        Ok((users, invalid))
+    }
+}
+
+#[async_trait]
+impl InteractionHandler for BulkScrimBan {
+    fn name(&self) -> String {
+        String::from("scrimbulk")
+    }
+
+    fn allowed_roles(&self) -> Option<Vec<RoleId>> {
+        Some(vec![crate::CONFIG.ss_support, crate::CONFIG.support])
+    }
+
+    async fn register(&self, ctx: &Context) -> crate::Result<()> {
+        CONFIG
+            .guild
+            .create_application_command(&ctx, |c| {
+                c.name(self.name())
+                    .description("Scrim bans or unbans many users at once")
+                    .default_member_permissions(Permissions::empty())
+                    .create_option(|ban| {
+                        ban.kind(command::CommandOptionType::SubCommand)
+                            .name("ban")
+                            .description("Bans every given user from playing scrims")
+                            .create_sub_option(|o| {
+                                o.name("reason")
+                                    .description("Reason for the bans")
+                                    .required(true)
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("users")
+                                    .description("The users to ban, as mentions or IDs")
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("file")
+                                    .description("A text file of user IDs to ban")
+                                    .kind(command::CommandOptionType::Attachment)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("duration")
+                                    .description("The ban duration (e.g. 10s 15m 20h 16w 20months 1y). [Default: based on past bans]")
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("permanent")
+                                    .description("Ban the users until they are unbanned manually")
+                                    .kind(command::CommandOptionType::Boolean)
+                            })
+                    })
+                    .create_option(|unban| {
+                        unban
+                            .kind(command::CommandOptionType::SubCommand)
+                            .name("unban")
+                            .description("Unbans every given user from scrims")
+                            .create_sub_option(|o| {
+                                o.name("reason")
+                                    .description("Reason for the unbans")
+                                    .required(true)
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("users")
+                                    .description("The users to unban, as mentions or IDs")
+                                    .kind(command::CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("file")
+                                    .description("A text file of user IDs to unban")
+                                    .kind(command::CommandOptionType::Attachment)
+                            })
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+
+    fn initial_response(
+        &self,
+        _interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        InitialInteractionResponse::DeferEphemeralReply
+    }
+
+    async fn handle_command(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> InteractionResult {
+        let options = &command.data.options[0];
+        let banning = options.name == "ban";
+        let executor = command.member.as_ref().unwrap();
+        let reason = options.get_str("reason").unwrap();
+        let length = if banning {
+            Some(BanLength::from_options(
+                options.get_str("duration"),
+                options.get_bool("permanent").unwrap_or(false),
+            )?)
+        } else {
+            None
+        };
+        let (users, invalid) = self.users(command, options).await?;
+
+        let mut done = Vec::new();
+        let mut failed = invalid
+            .into_iter()
+            .map(|part| format!("`{}`: not a user", part))
+            .collect::<Vec<_>>();
+        for (i, user) in users.iter().copied().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(PACING).await;
+            }
+            if i % PROGRESS_EVERY == 0 {
+                let _ = command
+                    .edit_original_interaction_response(&ctx, |r| {
+                        r.content(format!("Working... {}/{}", i, users.len()))
+                    })
+                    .await;
+            }
+
+            let res = match length {
+                Some(length) => async {
+                    let (unban_date, tier) = length.resolve(user).await?;
+                    check_can_ban(ctx, executor, user).await?;
+                    unfreeze_before_ban(ctx, user).await?;
+                    scrim_ban(
+                        ctx,
+                        user,
+                        executor.user.id,
+                        unban_date,
+                        reason.clone(),
+                        tier,
+                    )
+                    .await
+                }
+                .await
+                .map(|_| ()),
+                None => async {
+                    let unban = DATABASE
+                        .run(|db| db.fetch_scrim_unbans())
+                        .await?
+                        .into_iter()
+                        .find(|x| x.id == user.0)
+                        .ok_or_else(|| ErrorResponse::message("not banned"))?;
+                    scrim_unban(ctx, Some(executor.user.id), &unban, reason.clone()).await
+                }
+                .await
+                .map(|_| ()),
+            };
+            match res {
+                Ok(_) => done.push(user.mention().to_string()),
+                Err(err) => {
+                    tracing::warn!("Bulk scrim ban failed on {}: {}", user, err);
+                    failed.push(format!("{}: {}", user.mention(), error_text(err.as_ref())));
+                }
+            }
+        }
+
+        let (title, action) = if banning {
+            ("Bulk Scrim Ban", "Banned")
+        } else {
+            ("Bulk Scrim Unban", "Unbanned")
+        };
+        let mut resp = CreateInteractionResponseData::default();
+        resp.content("").embed(|e| {
+            e.title(title)
+                .description(format!(
+                    "{} {} of {} user(s).",
+                    action,
+                    done.len(),
+                    users.len()
+                ))
+                .field("Reason", format!("```{}```", reason), false)
+                .color(if failed.is_empty() {
+                    0x20BF72
+                } else {
+                    0xFD4659
+                });
+            if !done.is_empty() {
+                e.field(action, field_list(&done), false);
+            }
+            if !failed.is_empty() {
+                e.field("Failed", field_list(&failed), false);
+            }
+            e
+        });
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mentions_and_ids() {
+        let (ids, invalid) = parse_user_ids("<@1> <@!2>, 3\n4;5 1 nope <#6>");
+        assert_eq!(
+            ids,
+            vec![UserId(1), UserId(2), UserId(3), UserId(4), UserId(5)]
+        );
+        assert_eq!(invalid, vec!["nope", "<#6>"]);
+    }
+
+    #[test]
+    fn parse_nothing() {
+        let (ids, invalid) = parse_user_ids(" \n, ");
+        assert!(ids.is_empty() && invalid.is_empty());
+    }
+
+    #[test]
+    fn long_lists_are_cut() {
+        let lines = (0..100)
+            .map(|i| format!("<@{}>: not banned", 1_000_000 + i))
+            .collect::<Vec<_>>();
+        let value = field_list(&lines);
+        assert!(value.len() <= 1024);
+        assert!(value.ends_with("more*"));
+    }
+}
@@ -1,16 +1,35 @@
+use std::collections::HashSet;
+
 use futures::StreamExt;
 use serenity::{
     async_trait,
+    builder::CreateInteractionResponseData,
     client::Context,
     model::application::interaction::{
         application_command::ApplicationCommandInteraction,
-        message_component::MessageComponentInteraction,
+        message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
     },
     model::prelude::*,
 };
+use time::OffsetDateTime;
+
+use crate::db::{ClosedScreenshare, ScreenshareOutcome};
+use bridge_scrims::{interaction::*, transcript};
+
+/// Attachments are archived until they add up to this, so the archive stays below Discord's
+/// upload limit of 8 MB
+const MAX_ATTACHMENTS_SIZE: u64 = 7 * 1024 * 1024;
 
-use bridge_scrims::{interaction::*, print_embeds::FormatEmbed};
+/// Longest notes a screensharer can leave when closing
+const NOTES_LENGTH: u64 = 1000;
+/// Earlier screenshares of the same user listed in the log
+const MAX_HISTORY: usize = 5;
+/// Notes of earlier screenshares are cut off after this many characters, so that the list fits in
+/// an embed field
+const HISTORY_NOTES_LENGTH: usize = 120;
 
+/// Closes screenshare tickets. The command takes the outcome as an option, the Close button asks
+/// for it with a select menu and then for notes with a modal.
 pub struct Close;
 
 #[async_trait]
@@ -30,6 +49,21 @@ impl InteractionHandler for Close {
                 command
                     .name(self.name())
                     .description("Closes a screenshare")
+                    .create_option(|o| {
+                        o.name("outcome")
+                            .description("How the screenshare ended")
+                            .kind(command::CommandOptionType::String)
+                            .required(true);
+                        for outcome in ScreenshareOutcome::CHOOSABLE {
+                            o.add_string_choice(outcome.label(), outcome.as_str());
+                        }
+                        o
+                    })
+                    .create_option(|o| {
+                        o.name("notes")
+                            .description("Anything worth remembering about the screenshare")
+                            .kind(command::CommandOptionType::String)
+                    })
             })
             .await?;
         Ok(())
@@ -37,9 +71,13 @@ impl InteractionHandler for Close {
 
     fn initial_response(
         &self,
-        _interaction_type: interaction::InteractionType,
+        interaction_type: interaction::InteractionType,
     ) -> InitialInteractionResponse {
-        InitialInteractionResponse::DeferEphemeralReply
+        match interaction_type {
+            // The button is answered with the select menu and the select menu with the modal
+            interaction::InteractionType::MessageComponent => InitialInteractionResponse::None,
+            _ => InitialInteractionResponse::DeferEphemeralReply,
+        }
     }
 
     async fn handle_command(
@@ -47,7 +85,9 @@ impl InteractionHandler for Close {
         ctx: &Context,
         command: &ApplicationCommandInteraction,
     ) -> InteractionResult {
-        close_ticket(ctx, command.user.id, command.channel_id).await?;
+        let outcome = command.get_str("outcome").unwrap().parse()?;
+        let notes = command.get_str("notes");
+        close_ticket(ctx, command.user.id, command.channel_id, outcome, notes).await?;
         Ok(None)
     }
 
@@ -55,9 +95,71 @@ impl InteractionHandler for Close {
         &self,
         ctx: &Context,
         command: &MessageComponentInteraction,
-        _args: &[&str],
+        args: &[&str],
+    ) -> InteractionResult {
+        let channel = command.channel_id;
+        crate::consts::DATABASE
+            .run(move |db| db.fetch_screenshares_for(channel.0))
+            .await?
+            .ok_or_else(|| ErrorResponse::message("This channel isn't a screenshare ticket!"))?;
+
+        // The Close button
+        if args.is_empty() {
+            let mut resp = CreateInteractionResponseData::default();
+            resp.content("How did the screenshare end?")
+                .ephemeral(true)
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_select_menu(|menu| {
+                            menu.custom_id(format!("{}:outcome", self.name()))
+                                .placeholder("Outcome")
+                                .options(|o| {
+                                    for outcome in ScreenshareOutcome::CHOOSABLE {
+                                        o.create_option(|opt| {
+                                            opt.label(outcome.label()).value(outcome.as_str())
+                                        });
+                                    }
+                                    o
+                                })
+                        })
+                    })
+                });
+            return Ok(Some(resp));
+        }
+
+        let outcome: ScreenshareOutcome = command.data.values.first().unwrap().parse()?;
+        command
+            .create_interaction_response(&ctx, |r| {
+                r.kind(interaction::InteractionResponseType::Modal)
+                    .interaction_response_data(|d| {
+                        d.custom_id(format!("{}:{}", self.name(), outcome))
+                            .title(format!("Close Screenshare: {}", outcome.label()))
+                            .components(|c| {
+                                c.create_action_row(|row| {
+                                    row.create_input_text(|i| {
+                                        i.custom_id("notes")
+                                            .label("Notes")
+                                            .style(component::InputTextStyle::Paragraph)
+                                            .max_length(NOTES_LENGTH)
+                                            .required(false)
+                                    })
+                                })
+                            })
+                    })
+            })
+            .await?;
+        Ok(None)
+    }
+
+    async fn handle_modal(
+        &self,
+        ctx: &Context,
+        modal: &ModalSubmitInteraction,
+        args: &[&str],
     ) -> InteractionResult {
-        close_ticket(ctx, command.user.id, command.channel_id).await?;
+        let outcome = args.first().unwrap().parse()?;
+        let notes = modal.get_str("notes").filter(|notes| !notes.is_empty());
+        close_ticket(ctx, modal.user.id, modal.channel_id, outcome, notes).await?;
         Ok(None)
     }
 
@@ -66,53 +168,161 @@ impl InteractionHandler for Close {
     }
 }
 
-pub async fn close_ticket(ctx: &Context, closer: UserId, channel: ChannelId) -> crate::Result<()> {
+pub async fn close_ticket(
+    ctx: &Context,
+    closer: UserId,
+    channel: ChannelId,
+    outcome: ScreenshareOutcome,
+    notes: Option<String>,
+) -> crate::Result<()> {
     let screenshare = crate::consts::DATABASE
-        .fetch_screenshares_for(channel.0)
+        .run(move |db| db.fetch_screenshares_for(channel.0))
+        .await?
         .ok_or_else(|| ErrorResponse::message("This channel isn't a screenshare ticket!"))?;
+    let in_question = screenshare.in_question;
+    let (history, reporters) = crate::consts::DATABASE
+        .run(move |db| {
+            Ok((
+                db.fetch_screenshare_history_for(in_question)?,
+                db.fetch_screenshare_reporters(channel.0)?,
+            ))
+        })
+        .await?;
 
-    let mut messages = Vec::new();
-    let raw_messages = channel
-        .messages_iter(&ctx)
-        .boxed()
-        .collect::<Vec<_>>()
-        .await;
-
-    for message in raw_messages.into_iter().flatten() {
-        messages.push(format!(
-            "[{}] {}: {}",
-            message.timestamp,
-            message.author.tag(),
-            message.content_safe(ctx)
-        ));
-        for embed in message.embeds {
-            messages.push(format!("Embed:\n{}", FormatEmbed(embed.into())));
-        }
-    }
-
-    messages.reverse();
-    let history = messages.join("\n").into_bytes().into();
+    let transcript = transcript(ctx, channel).await?;
     crate::CONFIG
         .ss_logs
         .send_message(&ctx, |msg| {
             msg.files([AttachmentType::Bytes {
-                data: history,
-                filename: String::from("messages.txt"),
+                data: transcript.into(),
+                filename: format!("transcript-{}.zip", channel),
             }])
             .embed(|e| {
-                e.title("Screenshare closed").description(format!(
-                    "\
-                        - Creator: <@{}> \n\
-                        - In Question: <@{}> \n\
-                        - Closer: <@{}> \
-                    ",
-                    screenshare.creator, screenshare.in_question, closer
-                ))
+                e.title("Screenshare closed")
+                    .description(format!(
+                        "\
+                            - Creator: <@{}> \n\
+                            - In Question: <@{}> \n\
+                            - Assignee: {} \n\
+                            - Closer: <@{}> \
+                        ",
+                        screenshare.creator,
+                        screenshare.in_question,
+                        screenshare
+                            .assignee
+                            .map_or(String::from("Unclaimed"), |assignee| format!(
+                                "<@{}>",
+                                assignee
+                            )),
+                        closer
+                    ))
+                    .field("Outcome", outcome.label(), true);
+                if !reporters.is_empty() {
+                    let reporters = reporters
+                        .iter()
+                        .map(|reporter| {
+                            format!(
+                                "<@{}> <t:{}:R>",
+                                reporter.user,
+                                reporter.added_at.unix_timestamp()
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    e.field("Also Reported By", reporters.join("\n"), false);
+                }
+                if let Some(notes) = &notes {
+                    e.field("Notes", notes, false);
+                }
+                if !history.is_empty() {
+                    e.field("Earlier Screenshares", describe_history(&history), false);
+                }
+                e
             })
         })
         .await?;
 
-    crate::consts::DATABASE.remove_entry("Screenshares", channel.0)?;
+    let closed_at = OffsetDateTime::now_utc();
+    crate::consts::DATABASE
+        .run(move |db| {
+            db.close_screenshare(channel.0, closer.0, outcome, notes.as_deref(), closed_at)
+        })
+        .await?;
     channel.delete(&ctx).await?;
     Ok(())
 }
+
+/// Lists the outcomes of the user's earlier screenshares, newest first
+fn describe_history(history: &[ClosedScreenshare]) -> String {
+    let mut lines = history
+        .iter()
+        .take(MAX_HISTORY)
+        .map(|closed| {
+            let mut line = format!(
+                "<t:{}:d> {}",
+                closed.closed_at.unix_timestamp(),
+                closed.outcome.label()
+            );
+            if let Some(assignee) = closed.assignee {
+                line.push_str(&format!(" by <@{}>", assignee));
+            }
+            if let Some(notes) = &closed.notes {
+                let shown = notes.chars().take(HISTORY_NOTES_LENGTH).collect::<String>();
+                let cut = if shown.len() < notes.len() { "..." } else { "" };
+                line.push_str(&format!(": `{}{}`", shown, cut));
+            }
+            line
+        })
+        .collect::<Vec<_>>();
+    if history.len() > MAX_HISTORY {
+        lines.push(format!("...and {} more", history.len() - MAX_HISTORY));
+    }
+    lines.join("\n")
+}
+
+/// Zips an HTML transcript of the channel together with as many of its attachments as fit, so the
+/// evidence outlives the channel
+pub async fn transcript(ctx: &Context, channel: ChannelId) -> crate::Result<Vec<u8>> {
+    let mut messages = channel
+        .messages_iter(&ctx)
+        .boxed()
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<serenity::Result<Vec<_>>>()?;
+    messages.reverse();
+
+    let mut archived = HashSet::new();
+    let mut files = Vec::new();
+    let mut size = 0;
+    for message in &mut messages {
+        message.content = message.content_safe(ctx);
+        for attachment in &message.attachments {
+            if size + attachment.size > MAX_ATTACHMENTS_SIZE {
+                continue;
+            }
+            match attachment.download().await {
+                Ok(data) => {
+                    size += attachment.size;
+                    archived.insert(attachment.id);
+                    files.push((transcript::attachment_path(attachment), data));
+                }
+                Err(err) => tracing::warn!(
+                    "Failed to download attachment {} of channel {}: {}",
+                    attachment.id,
+                    channel,
+                    err
+                ),
+            }
+        }
+    }
+
+    let title = format!(
+        "#{}",
+        channel
+            .name(ctx)
+            .await
+            .unwrap_or_else(|| channel.to_string())
+    );
+    let html = transcript::render(&title, &messages, &archived);
+    transcript::archive(&html, &files)
+}
@@ -0,0 +1,115 @@
+use serenity::{
+    async_trait,
+    builder::{CreateEmbed, CreateInteractionResponseData},
+    client::Context,
+    model::application::interaction::message_component::MessageComponentInteraction,
+    model::prelude::*,
+};
+
+use super::ban::{check_can_ban, scrim_ban, unfreeze_before_ban, BanLength};
+use crate::consts::DATABASE;
+use bridge_scrims::interaction::*;
+
+/// The ban buttons on ban evasion alerts, which ban the new member for as long as the banned
+/// user they are an alt of
+pub struct Evasion;
+
+#[async_trait]
+impl InteractionHandler for Evasion {
+    fn name(&self) -> String {
+        String::from("evasion")
+    }
+
+    fn allowed_roles(&self) -> Option<Vec<RoleId>> {
+        Some(vec![
+            crate::CONFIG.ss_support,
+            crate::CONFIG.support,
+            crate::CONFIG.trial_support,
+        ])
+    }
+
+    async fn register(&self, _ctx: &Context) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn initial_response(
+        &self,
+        _interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        InitialInteractionResponse::DeferEphemeralReply
+    }
+
+    async fn handle_component(
+        &self,
+        ctx: &Context,
+        command: &MessageComponentInteraction,
+        args: &[&str],
+    ) -> InteractionResult {
+        let alt = UserId(args.first().unwrap().parse()?);
+        let banned = UserId(args.get(1).unwrap().parse()?);
+        let executor = command.member.as_ref().unwrap();
+
+        // The alt serves whatever is left of the ban, or a new one if that ran out in the meantime
+        let ban = DATABASE
+            .run(|db| db.fetch_scrim_unbans())
+            .await?
+            .into_iter()
+            .find(|x| x.id == banned.0 && !x.is_expired());
+        let (unban_date, tier) = match ban {
+            Some(ban) => (ban.date, None),
+            None => BanLength::Escalated.resolve(alt).await?,
+        };
+
+        check_can_ban(ctx, executor, alt).await?;
+        unfreeze_before_ban(ctx, alt).await?;
+        let embed = scrim_ban(
+            ctx,
+            alt,
+            executor.user.id,
+            unban_date,
+            format!("Ban evasion (alt of {})", banned.mention()),
+            tier,
+        )
+        .await?;
+
+        let mut alert = command
+            .message
+            .embeds
+            .first()
+            .cloned()
+            .map(CreateEmbed::from)
+            .unwrap_or_default();
+        alert
+            .field(
+                "Decision",
+                format!(
+                    "Banned by {} as an alt of {}",
+                    executor.mention(),
+                    banned.mention()
+                ),
+                false,
+            )
+            .color(0xFD4659);
+        let _ = command
+            .channel_id
+            .edit_message(ctx, command.message.id, |m| {
+                m.set_embed(alert).components(|c| c)
+            })
+            .await
+            .map_err(|err| tracing::error!("Failed to close ban evasion alert: {}", err));
+
+        tracing::info!(
+            "{} banned {} as an alt of {}",
+            executor.user.tag(),
+            alt,
+            banned
+        );
+        let mut resp = CreateInteractionResponseData::default();
+        resp.add_embed(embed);
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
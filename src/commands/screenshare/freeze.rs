@@ -11,6 +11,9 @@ use serenity::{
     model::prelude::*,
 };
 
+use super::stats::record_action;
+use crate::db::ScreenshareActionKind;
+use crate::features::frozen::Frozen;
 use bridge_scrims::interaction::*;
 
 pub struct Freeze;
@@ -103,7 +106,8 @@ async fn freeze_user<'a>(ctx: &Context, target: UserId, executor: UserId) -> Int
     }
 
     let is_frozen = crate::consts::DATABASE
-        .fetch_freezes_for(target.0)
+        .run(move |db| db.fetch_freezes_for(target.0))
+        .await?
         .is_some();
     if is_frozen {
         return Err(ErrorResponse::message(format!(
@@ -112,14 +116,20 @@ async fn freeze_user<'a>(ctx: &Context, target: UserId, executor: UserId) -> Int
         )))?;
     }
 
-    let is_banned = crate::consts::DATABASE
-        .fetch_scrim_unbans()
-        .iter()
-        .any(|x| !x.is_expired() && x.id == target.0);
-    if is_banned {
+    let ban = crate::consts::DATABASE
+        .run(|db| db.fetch_scrim_unbans())
+        .await?
+        .into_iter()
+        .find(|x| !x.is_expired() && x.id == target.0);
+    if let Some(ban) = ban {
         return Err(ErrorResponse::message(format!(
-            "{} is already banned.",
-            target.mention()
+            "{} is already {}.",
+            target.mention(),
+            if ban.is_permanent() {
+                "permanently banned"
+            } else {
+                "banned"
+            }
         )))?;
     }
 
@@ -135,13 +145,28 @@ async fn freeze_user<'a>(ctx: &Context, target: UserId, executor: UserId) -> Int
         .filter(|r| !new_roles.contains(r))
         .collect::<Vec<_>>();
 
-    member.edit(&ctx, |m| m.roles(new_roles)).await?;
-    let res = crate::consts::DATABASE.add_freeze(
-        target.0,
-        removed_roles.into(),
-        OffsetDateTime::now_utc(),
-    );
+    let (text, voice) = Frozen::create_channels(ctx, &member.user).await?;
+    let discard_channels = || async move {
+        let _ = text.delete(&ctx).await;
+        let _ = voice.delete(&ctx).await;
+    };
+    if let Err(err) = member.edit(&ctx, |m| m.roles(new_roles)).await {
+        discard_channels().await;
+        return Err(Box::new(err));
+    }
+    let frozen_at = OffsetDateTime::now_utc();
+    let res = crate::consts::DATABASE
+        .run(move |db| {
+            db.add_freeze(target.0, removed_roles.into(), frozen_at)?;
+            let res = db.set_freeze_channels(target.0, text.0, voice.0);
+            if res.is_err() {
+                let _ = db.remove_freeze(target.0);
+            }
+            res
+        })
+        .await;
     if let Err(err) = res {
+        discard_channels().await;
         // This is already a fail-safe so errors here are ignored
         let _ = member
             .edit(&ctx, |m| m.roles(targets_role_ids.clone()))
@@ -155,15 +180,20 @@ async fn freeze_user<'a>(ctx: &Context, target: UserId, executor: UserId) -> Int
         return Err(Box::new(err));
     }
 
+    let _ = Frozen::schedule_deadline(target, frozen_at)
+        .await
+        .map_err(|e| tracing::error!("Failed to schedule freeze deadline: {}", e));
+
+    link_ticket(ctx, target, text, voice).await;
+
     // This is ignored since at this point the user has already been frozen, thus it's too late to abort
-    let _ = crate::CONFIG
-        .frozen_chat
+    let _ = text
         .send_message(&ctx, |msg| {
             msg.content(format!(
                 "\
                     Hello {}, would you like to admit to cheating for a shortened ban or would \
-                    you like us to search through your computer for cheats? You have 5 minutes \
-                    to either admit in this channel or join {} and follow the instructions below.\
+                    you like us to search through your computer for cheats? You have {} minutes (until {}) \
+                    to either admit using the button below or join {} and follow the instructions below.\
                     \n \n\
                     **Download AnyDesk from here:** \n\
                     Windows: https://download.anydesk.com/AnyDesk.exe \n\
@@ -178,12 +208,52 @@ async fn freeze_user<'a>(ctx: &Context, target: UserId, executor: UserId) -> Int
                     Our screensharers **will __not__ be going through personal files or attempting to harm your computer**. \
                     We will only be checking for cheats by inspecting your mouse & keyboard software, recycle bin, deleted files \
                     and applications ran on this instance of your pc, as well as by running pre-bundled, trusted screenshare tools.\
-                ", target.mention(), crate::CONFIG.hello_cheaters.mention()
+                ",
+                target.mention(),
+                crate::CONFIG.freeze_deadline,
+                format_args!("<t:{}:t>", Frozen::deadline_for(frozen_at).unix_timestamp()),
+                voice.mention()
             )).flags(MessageFlags::SUPPRESS_EMBEDS)
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|b| {
+                        b.custom_id(format!("admit:{}", target))
+                            .label("I admit")
+                            .style(component::ButtonStyle::Danger)
+                    })
+                })
+            })
         }).await
             .map_err(|e| tracing::error!("Failed to send freeze message: {}", e));
+    record_action(executor.user.id, target, ScreenshareActionKind::Freeze).await;
 
     let mut response = CreateInteractionResponseData::default();
-    response.content(format!("Successfully froze {}.", target.mention()));
+    response.content(format!(
+        "Successfully froze {} in {}.",
+        target.mention(),
+        text.mention()
+    ));
     Ok(Some(response))
 }
+
+/// Points the open screenshare ticket of the user to the channels of their freeze
+async fn link_ticket(ctx: &Context, user: UserId, text: ChannelId, voice: ChannelId) {
+    let tickets = crate::consts::DATABASE
+        .run(|db| db.fetch_screenshares())
+        .await
+        .unwrap_or_default();
+    for ticket in tickets.iter().filter(|ticket| ticket.in_question == user.0) {
+        let _ = ChannelId(ticket.id)
+            .say(
+                &ctx,
+                format!(
+                    "{} was frozen, the screenshare continues in {} and {}.",
+                    user.mention(),
+                    text.mention(),
+                    voice.mention()
+                ),
+            )
+            .await
+            .map_err(|e| tracing::error!("Failed to link ticket to freeze: {}", e));
+    }
+}
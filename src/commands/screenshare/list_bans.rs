@@ -41,19 +41,24 @@ impl InteractionHandler for ListBans {
         ctx: &Context,
         command: &ApplicationCommandInteraction,
     ) -> InteractionResult {
-        let bans = crate::consts::DATABASE.fetch_scrim_unbans();
+        let bans = crate::consts::DATABASE
+            .run(|db| db.fetch_scrim_unbans())
+            .await?;
         let mut desc = vec![String::new()];
         for ban in bans.into_iter().filter(|b| !b.is_expired()) {
             if desc[desc.len() - 1].len() > 1950 {
                 desc.push(String::new())
             }
             let t = desc.len() - 1;
-            writeln!(
-                desc[t],
-                "- <@!{}>: banned until <t:{}:R>",
-                ban.id,
-                ban.date.unwrap().unix_timestamp()
-            )?;
+            match ban.date {
+                Some(date) => writeln!(
+                    desc[t],
+                    "- <@!{}>: banned until <t:{}:R>",
+                    ban.id,
+                    date.unix_timestamp()
+                )?,
+                None => writeln!(desc[t], "- <@!{}>: banned permanently", ban.id)?,
+            }
         }
         command
             .create_interaction_response(&ctx.http, |resp| {
@@ -0,0 +1,234 @@
+use serenity::{
+    async_trait,
+    builder::CreateInteractionResponseData,
+    client::Context,
+    model::application::interaction::{
+        application_command::ApplicationCommandInteraction,
+        message_component::MessageComponentInteraction,
+    },
+    model::prelude::*,
+};
+
+use crate::consts::{CONFIG, DATABASE};
+use crate::db::Screenshare;
+use bridge_scrims::interaction::*;
+
+/// Most tickets listed at once, the embed description fits 4096 characters
+const MAX_TICKETS: usize = 25;
+
+/// Shows the open screenshare tickets and who is handling them. Screensharers claim tickets with
+/// the Claim button or command, and can hand them to someone else or give them back.
+pub struct Queue;
+
+async fn ticket_in(channel: ChannelId) -> crate::Result<Screenshare> {
+    Ok(DATABASE
+        .run(move |db| db.fetch_screenshares_for(channel.0))
+        .await?
+        .ok_or_else(|| ErrorResponse::message("This channel isn't a screenshare ticket!"))?)
+}
+
+fn describe(ticket: &Screenshare) -> String {
+    format!(
+        "{} • {} • opened <t:{}:R> • {}",
+        ChannelId(ticket.id).mention(),
+        UserId(ticket.in_question).mention(),
+        ticket.created_at.unix_timestamp(),
+        ticket
+            .assignee
+            .map_or(String::from("**Unclaimed**"), |assignee| {
+                format!("claimed by {}", UserId(assignee).mention())
+            })
+    )
+}
+
+async fn claim<'a>(ctx: &Context, channel: ChannelId, staff: UserId) -> InteractionResult<'a> {
+    let ticket = ticket_in(channel).await?;
+    if ticket.assignee == Some(staff.0) {
+        return Err(ErrorResponse::message("You already claimed this ticket."))?;
+    }
+    let (id, assignee) = (ticket.id, staff.0);
+    if !DATABASE
+        .run(move |db| db.claim_screenshare(id, assignee))
+        .await?
+    {
+        return Err(ErrorResponse::message(format!(
+            "This ticket was already claimed by {}.",
+            ticket
+                .assignee
+                .map_or(String::from("someone else"), |assignee| {
+                    UserId(assignee).mention().to_string()
+                })
+        )))?;
+    }
+    tracing::info!("{} claimed ticket {}", staff, channel);
+
+    channel
+        .say(&ctx, format!("{} claimed this ticket.", staff.mention()))
+        .await?;
+    let mut resp = CreateInteractionResponseData::default();
+    resp.content("You claimed this ticket.");
+    Ok(Some(resp))
+}
+
+#[async_trait]
+impl InteractionHandler for Queue {
+    fn name(&self) -> String {
+        String::from("queue")
+    }
+
+    fn allowed_roles(&self) -> Option<Vec<RoleId>> {
+        Some(vec![CONFIG.ss_support])
+    }
+
+    async fn register(&self, ctx: &Context) -> crate::Result<()> {
+        CONFIG
+            .guild
+            .create_application_command(&ctx, |c| {
+                c.name(self.name())
+                    .description("Manages the screenshare queue")
+                    .default_member_permissions(Permissions::empty())
+                    .create_option(|list| {
+                        list.kind(command::CommandOptionType::SubCommand)
+                            .name("list")
+                            .description("Shows the open tickets, oldest first")
+                    })
+                    .create_option(|claim| {
+                        claim
+                            .kind(command::CommandOptionType::SubCommand)
+                            .name("claim")
+                            .description("Assigns this ticket to you")
+                    })
+                    .create_option(|unclaim| {
+                        unclaim
+                            .kind(command::CommandOptionType::SubCommand)
+                            .name("unclaim")
+                            .description("Puts this ticket back into the queue")
+                    })
+                    .create_option(|assign| {
+                        assign
+                            .kind(command::CommandOptionType::SubCommand)
+                            .name("assign")
+                            .description("Assigns this ticket to another screensharer")
+                            .create_sub_option(|o| {
+                                o.name("screensharer")
+                                    .description("Who should handle the ticket")
+                                    .required(true)
+                                    .kind(command::CommandOptionType::User)
+                            })
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+
+    fn initial_response(
+        &self,
+        _interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        InitialInteractionResponse::DeferEphemeralReply
+    }
+
+    async fn handle_command(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> InteractionResult {
+        let options = &command.data.options[0];
+        let channel = command.channel_id;
+        let staff = command.user.id;
+        let mut resp = CreateInteractionResponseData::default();
+        match options.name.as_str() {
+            "claim" => return claim(ctx, channel, staff).await,
+            "unclaim" => {
+                let ticket = ticket_in(channel).await?;
+                if ticket.assignee != Some(staff.0) {
+                    return Err(ErrorResponse::message(
+                        "Only the screensharer that claimed this ticket can unclaim it.",
+                    ))?;
+                }
+                let id = ticket.id;
+                DATABASE
+                    .run(move |db| db.assign_screenshare(id, None))
+                    .await?;
+                tracing::info!("{} unclaimed ticket {}", staff, channel);
+                channel
+                    .say(
+                        &ctx,
+                        format!(
+                            "{} unclaimed this ticket, {} it's back in the queue.",
+                            staff.mention(),
+                            CONFIG.ss_support.mention()
+                        ),
+                    )
+                    .await?;
+                resp.content("You unclaimed this ticket.");
+            }
+            "assign" => {
+                let ticket = ticket_in(channel).await?;
+                let assignee = UserId(options.get_str("screensharer").unwrap().parse()?);
+                let member = CONFIG.guild.member(&ctx, assignee).await?;
+                if !member.roles.contains(&CONFIG.ss_support) {
+                    return Err(ErrorResponse::message(format!(
+                        "{} isn't a screensharer.",
+                        assignee.mention()
+                    )))?;
+                }
+                let id = ticket.id;
+                DATABASE
+                    .run(move |db| db.assign_screenshare(id, Some(assignee.0)))
+                    .await?;
+                tracing::info!("{} assigned ticket {} to {}", staff, channel, assignee);
+                channel
+                    .say(
+                        &ctx,
+                        format!(
+                            "{} assigned this ticket to {}.",
+                            staff.mention(),
+                            assignee.mention()
+                        ),
+                    )
+                    .await?;
+                resp.content(format!("Assigned this ticket to {}.", assignee.mention()));
+            }
+            _ => {
+                let mut tickets = DATABASE.run(|db| db.fetch_screenshares()).await?;
+                tickets.sort_by_key(|ticket| ticket.created_at);
+                let unclaimed = tickets
+                    .iter()
+                    .filter(|ticket| ticket.assignee.is_none())
+                    .count();
+                let lines = tickets
+                    .iter()
+                    .take(MAX_TICKETS)
+                    .map(describe)
+                    .collect::<Vec<_>>();
+                resp.embed(|e| {
+                    e.title("Screenshare Queue")
+                        .description(if lines.is_empty() {
+                            String::from("*No open tickets*")
+                        } else {
+                            lines.join("\n")
+                        })
+                        .footer(|f| {
+                            f.text(format!("{} open, {} unclaimed", tickets.len(), unclaimed))
+                        })
+                        .color(0x0E87CC)
+                });
+            }
+        }
+        Ok(Some(resp))
+    }
+
+    async fn handle_component(
+        &self,
+        ctx: &Context,
+        command: &MessageComponentInteraction,
+        _args: &[&str],
+    ) -> InteractionResult {
+        claim(ctx, command.channel_id, command.user.id).await
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
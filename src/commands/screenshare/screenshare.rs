@@ -1,5 +1,5 @@
-use std::time::Duration;
-use tokio::time::sleep;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use time::OffsetDateTime;
 
 use serenity::{
     async_trait,
@@ -12,10 +12,30 @@ use serenity::{
 use bridge_scrims::interaction::*;
 
 use super::close;
+use crate::db::{Job, ScreenshareOutcome};
+use crate::features::scheduler::Scheduler;
+
+/// Tickets are closed this long after they were opened, unless the user got frozen by then
+const TICKET_TIMEOUT: Duration = Duration::from_secs(15 * 60);
 
 lazy_static::lazy_static! {
     pub static ref ALLOW_PERMS: Permissions = Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY | Permissions::SEND_MESSAGES;
     pub static ref DENY_PERMS: Permissions = Permissions::empty();
+    /// Held while a ticket for the user is being opened
+    static ref OPENING: std::sync::Mutex<HashMap<u64, Arc<tokio::sync::Mutex<()>>>> =
+        Default::default();
+}
+
+/// Makes reports of the same user wait for each other, so that everyone reporting them at once
+/// ends up in one ticket
+async fn lock_target(user: UserId) -> tokio::sync::OwnedMutexGuard<()> {
+    let lock = {
+        let mut locks = OPENING.lock().unwrap();
+        // Only the map holds locks that nobody is waiting for anymore
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(user.0).or_default().clone()
+    };
+    lock.lock_owned().await
 }
 
 pub struct Screenshare;
@@ -26,6 +46,10 @@ impl InteractionHandler for Screenshare {
         String::from("screenshare")
     }
 
+    async fn init(&self, ctx: &Context) {
+        tokio::spawn(reconcile_tickets(ctx.clone()));
+    }
+
     async fn register(&self, ctx: &Context) -> crate::Result<()> {
         crate::CONFIG
             .guild
@@ -64,13 +88,28 @@ impl InteractionHandler for Screenshare {
         command: &ApplicationCommandInteraction,
     ) -> InteractionResult {
         let in_question = UserId(command.get_str("user").unwrap().parse()?);
-        let screenshare = crate::consts::DATABASE.fetch_screenshares_for(command.user.id.0);
-        if let Some(screenshare) = screenshare {
+        let creator = command.user.id.0;
+        let _opening = lock_target(in_question).await;
+        let tickets = crate::consts::DATABASE
+            .run(|db| db.fetch_screenshares())
+            .await?;
+        // Everyone reporting the same user ends up in the same ticket
+        if let Some(ticket) = tickets.iter().find(|t| t.in_question == in_question.0) {
+            let ign = command.get_str("ign").unwrap();
+            return add_reporter(ctx, ticket, command.user.id, ign).await;
+        }
+        let open = tickets
+            .iter()
+            .filter(|t| t.creator == creator)
+            .map(|t| ChannelId(t.id).mention().to_string())
+            .collect::<Vec<_>>();
+        if open.len() >= crate::CONFIG.max_screenshare_requests {
             return Err(ErrorResponse::with_title(
-                "One at a time please",
+                "Too many requests",
                 format!(
-                    "You already have a screenshare request open at <#{}>.",
-                    screenshare.id
+                    "You can have at most {} screenshare request(s) open at once, yours are at {}.",
+                    crate::CONFIG.max_screenshare_requests,
+                    open.join(", ")
                 ),
             ))?;
         }
@@ -82,6 +121,9 @@ impl InteractionHandler for Screenshare {
             })?;
 
         let ign = command.get_str("ign").unwrap();
+        let _ = crate::commands::link::link_ign(in_question, &ign, command.user.id)
+            .await
+            .map_err(|err| tracing::error!("Failed to link {} to {}: {}", ign, in_question, err));
 
         let message = channel
             .send_message(&ctx, |m| {
@@ -100,8 +142,17 @@ impl InteractionHandler for Screenshare {
             return Err(Box::new(err));
         }
 
-        let res =
-            crate::consts::DATABASE.add_screenshare(channel.id.0, command.user.id.0, in_question.0);
+        let channel_id = channel.id.0;
+        let res = crate::consts::DATABASE
+            .run(move |db| {
+                db.add_screenshare(
+                    channel_id,
+                    creator,
+                    in_question.0,
+                    OffsetDateTime::now_utc(),
+                )
+            })
+            .await;
         if let Err(err) = res {
             let _ = channel
                 .send_message(
@@ -111,12 +162,14 @@ impl InteractionHandler for Screenshare {
             tracing::error!("Failed to add screenshare to database: {}", err)
         }
 
-        tokio::spawn(ticket_timeout(
-            ctx.clone(),
+        let _ = schedule_timeout(
+            channel.id,
             in_question,
             command.user.id,
-            channel.id,
-        ));
+            OffsetDateTime::now_utc(),
+        )
+        .await
+        .map_err(|err| tracing::error!("Failed to schedule ticket timeout: {}", err));
 
         let mut resp = CreateInteractionResponseData::default();
         resp.content(format!("Ticket created at {}.", channel.mention()));
@@ -128,17 +181,146 @@ impl InteractionHandler for Screenshare {
     }
 }
 
-async fn ticket_timeout(ctx: Context, in_question: UserId, closer: UserId, channel: ChannelId) {
-    sleep(Duration::from_secs(15 * 60)).await;
-    let not_frozen = crate::consts::DATABASE
-        .fetch_freezes_for(in_question.0)
-        .is_none();
-    if not_frozen {
-        let result = close::close_ticket(&ctx, closer, channel).await;
-        if let Err(err) = result {
-            tracing::error!("Failed to close ticket: {}", err)
+/// Adds someone reporting a user that already has an open ticket to that ticket
+async fn add_reporter<'a>(
+    ctx: &Context,
+    ticket: &crate::db::Screenshare,
+    reporter: UserId,
+    ign: String,
+) -> InteractionResult<'a> {
+    let channel = ChannelId(ticket.id);
+    let already_reported = ErrorResponse::message(format!(
+        "You already reported {} at {}.",
+        UserId(ticket.in_question).mention(),
+        channel.mention()
+    ));
+    if ticket.creator == reporter.0 {
+        return Err(already_reported)?;
+    }
+    let (id, user) = (ticket.id, reporter.0);
+    let added = crate::consts::DATABASE
+        .run(move |db| db.add_screenshare_reporter(id, user, OffsetDateTime::now_utc()))
+        .await?;
+    if !added {
+        return Err(already_reported)?;
+    }
+
+    channel
+        .create_permission(
+            &ctx,
+            &PermissionOverwrite {
+                allow: *ALLOW_PERMS,
+                deny: *DENY_PERMS,
+                kind: PermissionOverwriteType::Member(reporter),
+            },
+        )
+        .await?;
+    let in_question = UserId(ticket.in_question);
+    let _ = crate::commands::link::link_ign(in_question, &ign, reporter)
+        .await
+        .map_err(|err| tracing::error!("Failed to link {} to {}: {}", ign, in_question, err));
+    channel
+        .say(
+            &ctx,
+            format!(
+                "{} also reported {} (`{}`). Please send us any proof you have as well.",
+                reporter.mention(),
+                in_question.mention(),
+                ign
+            ),
+        )
+        .await?;
+
+    let mut resp = CreateInteractionResponseData::default();
+    resp.content(format!(
+        "{} already has an open ticket, you were added to {}.",
+        in_question.mention(),
+        channel.mention()
+    ));
+    Ok(Some(resp))
+}
+
+/// Closes the ticket once it times out. Scheduling it again for the same ticket does nothing.
+async fn schedule_timeout(
+    channel: ChannelId,
+    in_question: UserId,
+    creator: UserId,
+    created_at: OffsetDateTime,
+) -> crate::Result<bool> {
+    Scheduler::schedule(
+        Some(format!("ticket_timeout:{}", channel)),
+        Job::TicketTimeout {
+            channel: channel.0,
+            in_question: in_question.0,
+            creator: creator.0,
+        },
+        created_at + TICKET_TIMEOUT,
+    )
+    .await
+}
+
+/// Makes sure every open ticket times out, including ones opened before timeouts were scheduled.
+/// Tickets whose channel was deleted while the bot was offline are forgotten.
+async fn reconcile_tickets(ctx: Context) {
+    let tickets = match crate::consts::DATABASE
+        .run(|db| db.fetch_screenshares())
+        .await
+    {
+        Ok(tickets) => tickets,
+        Err(err) => {
+            tracing::error!("Failed to fetch open screenshare tickets: {}", err);
+            return;
         }
+    };
+
+    for ticket in tickets {
+        let channel = ChannelId(ticket.id);
+        if ctx.cache.guild_channel(channel).is_none() {
+            tracing::info!(
+                "Removing screenshare ticket {} since its channel is gone",
+                channel
+            );
+            let _ = crate::consts::DATABASE
+                .run(move |db| db.remove_screenshare(channel.0))
+                .await
+                .map_err(|err| tracing::error!("Failed to remove screenshare ticket: {}", err));
+            continue;
+        }
+
+        let _ = schedule_timeout(
+            channel,
+            UserId(ticket.in_question),
+            UserId(ticket.creator),
+            ticket.created_at,
+        )
+        .await
+        .map_err(|err| tracing::error!("Failed to schedule ticket timeout: {}", err));
+    }
+}
+
+/// Closes the ticket unless the user in question got frozen, run by the scheduler
+pub async fn ticket_timeout(
+    ctx: &Context,
+    in_question: UserId,
+    closer: UserId,
+    channel: ChannelId,
+) -> crate::Result<()> {
+    // Closed by hand in the meantime
+    if crate::consts::DATABASE
+        .run(move |db| db.fetch_screenshares_for(channel.0))
+        .await?
+        .is_none()
+    {
+        return Ok(());
     }
+    let frozen = crate::consts::DATABASE
+        .run(move |db| db.fetch_freezes_for(in_question.0))
+        .await?
+        .is_some();
+    if !frozen {
+        close::close_ticket(ctx, closer, channel, ScreenshareOutcome::TimedOut, None).await?;
+    }
+    Ok(())
 }
 
 async fn create_screenshare_ticket(
@@ -156,17 +338,14 @@ async fn create_screenshare_ticket(
             "Screenshare category does not exist!",
         ))?;
 
-    let mut count: Option<i64> = None;
-    crate::consts::DATABASE.count_rows("Screenshares", "", |val| {
-        if let sqlite::Value::Integer(co) = val[0] {
-            count = Some(co);
-        }
-    });
+    let count = crate::consts::DATABASE
+        .run(|db| db.count_screenshares())
+        .await?;
 
     let guild_channel = crate::CONFIG
         .guild
         .create_channel(&ctx.http, |ch| {
-            ch.name(format!("screenshare-{}", count.unwrap_or_default() + 1))
+            ch.name(format!("screenshare-{}", count + 1))
                 .category(category.0)
                 .kind(ChannelType::Text)
                 .permissions(
@@ -224,6 +403,12 @@ fn screenshare_message<'a>(creator: UserId, in_question: UserId, ign: String) ->
                     .style(component::ButtonStyle::Primary)
                     .emoji(ReactionType::try_from(crate::CONFIG.freeze_emoji.clone()).unwrap())
             })
+            .create_button(|button| {
+                button
+                    .label("Claim")
+                    .custom_id("queue:claim")
+                    .style(component::ButtonStyle::Secondary)
+            })
             .create_button(|button| {
                 button
                     .label("Close")
@@ -8,6 +8,8 @@ use serenity::{
 use crate::consts::{CONFIG, DATABASE};
 use bridge_scrims::interaction::*;
 
+/// The old unfreeze leaderboard. It only knows the unfreeze counter kept by `add_screensharer`,
+/// `/ssstats` has the full stats per screensharer and window.
 pub struct Screensharers;
 
 #[async_trait]
@@ -21,7 +23,7 @@ impl InteractionHandler for Screensharers {
             .guild
             .create_application_command(&ctx.http, |cmd| {
                 cmd.name(self.name()).description(
-                    "Lists the screenshare team and how much they've unfrozen someone.",
+                    "Lists how often screensharers unfroze someone, see /ssstats for full stats.",
                 )
             })
             .await?;
@@ -33,16 +35,15 @@ impl InteractionHandler for Screensharers {
         ctx: &Context,
         command: &ApplicationCommandInteraction,
     ) -> InteractionResult {
-        let screensharers = join_all(DATABASE.get_screensharers().into_iter().map(
-            |x| async move {
-                let user = UserId(x.id).to_user(&ctx.http).await;
-                if let Ok(user) = user {
-                    Some((user.tag(), format!("{} freezes", x.freezes), false))
-                } else {
-                    None
-                }
-            },
-        ))
+        let screensharers = DATABASE.run(|db| db.get_screensharers()).await?;
+        let screensharers = join_all(screensharers.into_iter().map(|x| async move {
+            let user = UserId(x.id).to_user(&ctx.http).await;
+            if let Ok(user) = user {
+                Some((user.tag(), format!("{} unfreezes", x.freezes), false))
+            } else {
+                None
+            }
+        }))
         .await
         .into_iter()
         .flatten();
@@ -51,7 +52,7 @@ impl InteractionHandler for Screensharers {
             resp.interaction_response_data(|data| {
                 data.embed(|embed| {
                     embed.title("Unfreeze Leaderboard")
-                        .description("List of every screenshare member that has unfrozen someone before and how many times they did it.")
+                        .description("List of every screenshare member that has unfrozen someone before and how many times they did it. Use /ssstats for freezes, tickets, bans and response times.")
                         .fields(screensharers)
                 })
             })
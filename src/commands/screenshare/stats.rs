@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+
+use serenity::{
+    async_trait, builder::CreateInteractionResponseData, client::Context,
+    model::application::interaction::application_command::ApplicationCommandInteraction,
+    model::prelude::*,
+};
+use time::OffsetDateTime;
+
+use crate::consts::{CONFIG, DATABASE};
+use crate::db::{ClosedScreenshare, ScreenshareAction, ScreenshareActionKind, ScreenshareOutcome};
+use bridge_scrims::interaction::*;
+
+/// Most screensharers shown in the overview, every one is a line of the description
+const MAX_SCREENSHARERS: usize = 25;
+
+/// The period stats are counted over
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Window {
+    Week,
+    Month,
+    AllTime,
+}
+
+impl Window {
+    const ALL: [Self; 3] = [Self::Week, Self::Month, Self::AllTime];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::AllTime => "all",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Week => "Last 7 Days",
+            Self::Month => "Last 30 Days",
+            Self::AllTime => "All Time",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|window| window.as_str() == s)
+    }
+
+    /// When the window starts
+    pub fn since(&self, now: OffsetDateTime) -> OffsetDateTime {
+        match self {
+            Self::Week => now - time::Duration::days(7),
+            Self::Month => now - time::Duration::days(30),
+            Self::AllTime => OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+}
+
+/// What one screensharer did within a window
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ScreensharerStats {
+    pub staff: u64,
+    pub freezes: u64,
+    pub unfreezes: u64,
+    /// Tickets they closed, not counting ones that timed out
+    pub tickets: u64,
+    /// Tickets they closed that ended in a ban, which includes admissions
+    pub bans: u64,
+    pub outcomes: HashMap<&'static str, u64>,
+    /// Seconds from a ticket being opened until they froze the user, sorted
+    pub responses: Vec<u64>,
+}
+
+impl ScreensharerStats {
+    pub fn median_response(&self) -> Option<u64> {
+        let len = self.responses.len();
+        if len == 0 {
+            return None;
+        }
+        // Both are the middle one for odd lengths
+        Some((self.responses[(len - 1) / 2] + self.responses[len / 2]) / 2)
+    }
+}
+
+fn entry(stats: &mut HashMap<u64, ScreensharerStats>, staff: u64) -> &mut ScreensharerStats {
+    stats.entry(staff).or_insert_with(|| ScreensharerStats {
+        staff,
+        ..Default::default()
+    })
+}
+
+/// Counts the actions and closed tickets per screensharer, busiest first
+pub fn tally(
+    actions: &[ScreenshareAction],
+    history: &[ClosedScreenshare],
+) -> Vec<ScreensharerStats> {
+    let mut stats: HashMap<u64, ScreensharerStats> = HashMap::new();
+    for action in actions {
+        let entry = entry(&mut stats, action.staff);
+        match action.kind {
+            ScreenshareActionKind::Freeze => {
+                entry.freezes += 1;
+                entry.responses.extend(action.response);
+            }
+            ScreenshareActionKind::Unfreeze => entry.unfreezes += 1,
+        }
+    }
+    for closed in history {
+        if closed.outcome == ScreenshareOutcome::TimedOut {
+            continue;
+        }
+        let entry = entry(&mut stats, closed.closer);
+        entry.tickets += 1;
+        if matches!(
+            closed.outcome,
+            ScreenshareOutcome::Banned | ScreenshareOutcome::Admitted
+        ) {
+            entry.bans += 1;
+        }
+        *entry.outcomes.entry(closed.outcome.label()).or_default() += 1;
+    }
+
+    let mut stats = stats.into_values().collect::<Vec<_>>();
+    for entry in &mut stats {
+        entry.responses.sort_unstable();
+    }
+    stats.sort_by_key(|entry| {
+        (
+            std::cmp::Reverse(entry.tickets + entry.freezes),
+            entry.staff,
+        )
+    });
+    stats
+}
+
+/// Like `3m 12s`
+fn format_seconds(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m {}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+fn median(stats: &ScreensharerStats) -> String {
+    stats
+        .median_response()
+        .map_or(String::from("-"), format_seconds)
+}
+
+/// Counts the action towards the screensharer's stats. Freezes of users with an open ticket also
+/// count how long it took to respond to the ticket.
+pub async fn record_action(staff: UserId, user: UserId, kind: ScreenshareActionKind) {
+    let _ = DATABASE
+        .run(move |db| {
+            let now = OffsetDateTime::now_utc();
+            let response = match kind {
+                ScreenshareActionKind::Freeze => db
+                    .fetch_screenshares()?
+                    .into_iter()
+                    .filter(|ticket| ticket.in_question == user.0)
+                    .map(|ticket| (now - ticket.created_at).whole_seconds().max(0) as u64)
+                    .min(),
+                ScreenshareActionKind::Unfreeze => None,
+            };
+            db.add_screenshare_action(staff.0, user.0, kind, now, response)
+        })
+        .await
+        .map_err(|err| tracing::error!("Failed to record screenshare {}: {}", kind, err));
+}
+
+pub struct SsStats;
+
+#[async_trait]
+impl InteractionHandler for SsStats {
+    fn name(&self) -> String {
+        String::from("ssstats")
+    }
+
+    fn allowed_roles(&self) -> Option<Vec<RoleId>> {
+        Some(vec![
+            crate::CONFIG.ss_support,
+            crate::CONFIG.support,
+            crate::CONFIG.trial_support,
+        ])
+    }
+
+    async fn register(&self, ctx: &Context) -> crate::Result<()> {
+        CONFIG
+            .guild
+            .create_application_command(&ctx, |c| {
+                c.name(self.name())
+                    .description("Shows what the screenshare team has been doing")
+                    .default_member_permissions(Permissions::empty())
+                    .create_option(|o| {
+                        o.name("window")
+                            .description("The period to count [Default: Last 7 Days]")
+                            .kind(command::CommandOptionType::String);
+                        for window in Window::ALL {
+                            o.add_string_choice(window.label(), window.as_str());
+                        }
+                        o
+                    })
+                    .create_option(|o| {
+                        o.name("user")
+                            .description("Shows the stats of a single screensharer")
+                            .kind(command::CommandOptionType::User)
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+
+    fn initial_response(
+        &self,
+        _interaction_type: interaction::InteractionType,
+    ) -> InitialInteractionResponse {
+        InitialInteractionResponse::DeferEphemeralReply
+    }
+
+    async fn handle_command(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> InteractionResult {
+        let window = command
+            .get_str("window")
+            .and_then(|window| Window::parse(&window))
+            .unwrap_or(Window::Week);
+        let user = match command.get_str("user") {
+            Some(user) => Some(UserId(user.parse()?)),
+            None => None,
+        };
+
+        let since = window.since(OffsetDateTime::now_utc());
+        let (actions, history) = DATABASE
+            .run(move |db| {
+                Ok((
+                    db.fetch_screenshare_actions(since)?,
+                    db.fetch_screenshare_history(since)?,
+                ))
+            })
+            .await?;
+        let stats = tally(&actions, &history);
+
+        let mut resp = CreateInteractionResponseData::default();
+        match user {
+            Some(user) => {
+                let stats = stats
+                    .into_iter()
+                    .find(|stats| stats.staff == user.0)
+                    .unwrap_or(ScreensharerStats {
+                        staff: user.0,
+                        ..Default::default()
+                    });
+                let mut outcomes = ScreenshareOutcome::CHOOSABLE
+                    .iter()
+                    .map(|outcome| {
+                        format!(
+                            "{}: {}",
+                            outcome.label(),
+                            stats.outcomes.get(outcome.label()).unwrap_or(&0)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                if stats.tickets == 0 {
+                    outcomes = vec![String::from("*No tickets*")];
+                }
+                resp.embed(|e| {
+                    e.title(format!("Screenshare Stats: {}", window.label()))
+                        .description(user.mention())
+                        .field("Freezes", stats.freezes, true)
+                        .field("Unfreezes", stats.unfreezes, true)
+                        .field("Tickets", stats.tickets, true)
+                        .field("Bans", stats.bans, true)
+                        .field("Median Response", median(&stats), true)
+                        .field("Outcomes", outcomes.join("\n"), false)
+                        .color(0x0E87CC)
+                });
+            }
+            None => {
+                let lines = stats
+                    .iter()
+                    .take(MAX_SCREENSHARERS)
+                    .map(|stats| {
+                        format!(
+                            "{} • {} freezes • {} unfreezes • {} tickets • {} bans • {} median",
+                            UserId(stats.staff).mention(),
+                            stats.freezes,
+                            stats.unfreezes,
+                            stats.tickets,
+                            stats.bans,
+                            median(stats)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                resp.embed(|e| {
+                    e.title(format!("Screenshare Stats: {}", window.label()))
+                        .description(if lines.is_empty() {
+                            String::from("*Nothing happened*")
+                        } else {
+                            lines.join("\n")
+                        })
+                        .footer(|f| {
+                            f.text(format!(
+                                "{} freezes, {} tickets closed",
+                                actions
+                                    .iter()
+                                    .filter(|a| a.kind == ScreenshareActionKind::Freeze)
+                                    .count(),
+                                history
+                                    .iter()
+                                    .filter(|closed| closed.outcome != ScreenshareOutcome::TimedOut)
+                                    .count()
+                            ))
+                        })
+                        .color(0x0E87CC)
+                });
+            }
+        }
+        Ok(Some(resp))
+    }
+
+    fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(staff: u64, kind: ScreenshareActionKind, response: Option<u64>) -> ScreenshareAction {
+        ScreenshareAction {
+            staff,
+            kind,
+            response,
+        }
+    }
+
+    fn closed(closer: u64, outcome: ScreenshareOutcome) -> ClosedScreenshare {
+        ClosedScreenshare {
+            closer,
+            assignee: None,
+            outcome,
+            notes: None,
+            closed_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    #[test]
+    fn counts_per_screensharer() {
+        let actions = [
+            action(1, ScreenshareActionKind::Freeze, Some(300)),
+            action(1, ScreenshareActionKind::Freeze, Some(60)),
+            action(1, ScreenshareActionKind::Freeze, None),
+            action(1, ScreenshareActionKind::Freeze, Some(120)),
+            action(2, ScreenshareActionKind::Unfreeze, None),
+        ];
+        let history = [
+            closed(1, ScreenshareOutcome::Banned),
+            closed(1, ScreenshareOutcome::Admitted),
+            closed(2, ScreenshareOutcome::Cleared),
+            // the creator closes tickets that time out
+            closed(3, ScreenshareOutcome::TimedOut),
+        ];
+        let stats = tally(&actions, &history);
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].staff, 1);
+        assert_eq!(
+            (stats[0].freezes, stats[0].tickets, stats[0].bans),
+            (4, 2, 2)
+        );
+        assert_eq!(stats[0].median_response(), Some(120));
+        assert_eq!(stats[0].outcomes["Admitted"], 1);
+        assert_eq!(
+            (stats[1].unfreezes, stats[1].tickets, stats[1].bans),
+            (1, 1, 0)
+        );
+        assert_eq!(stats[1].median_response(), None);
+    }
+
+    #[test]
+    fn medians() {
+        let stats = |responses: Vec<u64>| ScreensharerStats {
+            responses,
+            ..Default::default()
+        };
+        assert_eq!(stats(vec![10, 20]).median_response(), Some(15));
+        assert_eq!(stats(vec![10, 20, 90]).median_response(), Some(20));
+        assert_eq!(format_seconds(45), "45s");
+        assert_eq!(format_seconds(192), "3m 12s");
+        assert_eq!(format_seconds(7260), "2h 1m");
+    }
+}
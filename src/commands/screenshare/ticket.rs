@@ -59,7 +59,12 @@ impl InteractionHandler for Ticket {
             .guild()
             .unwrap();
 
-        if DATABASE.fetch_screenshares_for(channel.id.0).is_none() {
+        let channel_id = channel.id.0;
+        if DATABASE
+            .run(move |db| db.fetch_screenshares_for(channel_id))
+            .await?
+            .is_none()
+        {
             command
                 .create_interaction_response(&ctx.http, |resp| {
                     resp.interaction_response_data(|data| {
@@ -1,4 +1,5 @@
-use std::{collections::HashSet, time::Duration};
+use std::collections::HashSet;
+use time::OffsetDateTime;
 
 use serenity::{
     async_trait,
@@ -27,10 +28,6 @@ impl InteractionHandler for ScrimUnban {
         ])
     }
 
-    async fn init(&self, ctx: &Context) {
-        tokio::spawn(scrim_unban_update_loop(ctx.clone()));
-    }
-
     async fn register(&self, ctx: &Context) -> crate::Result<()> {
         CONFIG
             .guild
@@ -74,7 +71,8 @@ impl InteractionHandler for ScrimUnban {
             .unwrap_or_else(|| String::from("No reason provided"));
 
         let unban = DATABASE
-            .fetch_scrim_unbans()
+            .run(|db| db.fetch_scrim_unbans())
+            .await?
             .into_iter()
             .find(|x| x.id == user_id.0)
             .ok_or_else(|| ErrorResponse::message(format!("{} is not banned.", user)))?;
@@ -105,6 +103,7 @@ pub async fn scrim_unban(
         fields.push(("Staff", staff_id.mention().to_string(), true));
     }
     fields.push(("Reason", format!("```{}```", reason), false));
+    let revoke_reason = reason.clone();
 
     let mut embed = CreateEmbed::default();
     embed
@@ -153,7 +152,8 @@ pub async fn scrim_unban(
 
     let member = CONFIG.guild.member(ctx, to_unban.id).await;
     if let Ok(member) = member {
-        DATABASE.remove_entry("ScheduledScrimUnbans", unban.id)?;
+        let id = unban.id;
+        DATABASE.run(move |db| db.remove_scrim_unban(id)).await?;
 
         let taken_roles: Vec<RoleId> = db::Ids(unban.roles.0.clone()).into();
         let new_roles = taken_roles
@@ -174,8 +174,16 @@ pub async fn scrim_unban(
 
         let res = member.edit(ctx, |m| m.roles(new_roles)).await;
         if res.is_err() {
+            let (date, roles, logged) = (unban.date, db::Ids(unban.roles.0.clone()), unban.logged);
             let _ = DATABASE
-                .add_scrim_unban(unban.id, unban.date, &unban.roles)
+                .run(move |db| {
+                    db.add_scrim_unban(id, date, &roles)?;
+                    if logged {
+                        db.mark_scrim_unban_logged(id)?;
+                    }
+                    Ok(())
+                })
+                .await
                 .map_err(|err| {
                     tracing::error!(
                         "Failed to re-add scrim unban after giving back roles back failed: {}",
@@ -184,6 +192,7 @@ pub async fn scrim_unban(
                 });
             res?;
         }
+        record_unban(unban.id, staff_id, revoke_reason).await;
         let _ = log_unban.await;
     } else {
         if unban.was_logged() {
@@ -195,34 +204,45 @@ pub async fn scrim_unban(
         }
 
         log_unban.await?;
+        let id = unban.id;
         let _ = DATABASE
-            .exec(format!(
-                "UPDATE 'ScheduledScrimUnbans' SET time = NULL WHERE id = {}",
-                unban.id,
-            ))
+            .run(move |db| db.mark_scrim_unban_logged(id))
+            .await
             .map_err(|err| tracing::error!("Failed to mark scrims ban as logged: {}", err));
+        record_unban(unban.id, staff_id, revoke_reason).await;
     }
 
     Ok(embed)
 }
 
-async fn scrim_unban_update_loop(ctx: Context) {
-    let database = &crate::consts::DATABASE;
-    loop {
-        for unban in database.fetch_scrim_unbans() {
-            if unban.is_expired() {
-                let target = unban.id;
-                let res = scrim_unban(&ctx, None, &unban, String::from("Ban Expired")).await;
-                if let Err(err) = res {
-                    tracing::error!(
-                        "Failed to unban {} from scrims upon expiration: {}",
-                        target,
-                        err
-                    );
-                }
+async fn record_unban(user: u64, staff_id: Option<UserId>, reason: String) {
+    let staff = staff_id.map(|id| id.0);
+    let _ = DATABASE
+        .run(move |db| db.revoke_scrim_bans(user, staff, &reason, OffsetDateTime::now_utc()))
+        .await
+        .map_err(|err| tracing::error!("Failed to record scrim unban in history: {}", err));
+}
+
+/// Lifts every scrim ban that ran out, run by the scheduler
+pub async fn unban_expired(ctx: &Context) -> crate::Result<()> {
+    let unbans = DATABASE.run(|db| db.fetch_scrim_unbans()).await?;
+    let mut failed = 0;
+    for unban in unbans {
+        if unban.is_expired() {
+            let target = unban.id;
+            let res = scrim_unban(ctx, None, &unban, String::from("Ban Expired")).await;
+            if let Err(err) = res {
+                tracing::error!(
+                    "Failed to unban {} from scrims upon expiration: {}",
+                    target,
+                    err
+                );
+                failed += 1;
             }
         }
-
-        tokio::time::sleep(Duration::from_secs(3 * 60)).await;
     }
+    if failed > 0 {
+        return Err(format!("Failed to lift {} expired scrim bans", failed).into());
+    }
+    Ok(())
 }
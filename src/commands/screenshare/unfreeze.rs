@@ -4,7 +4,10 @@ use serenity::{
     model::prelude::*,
 };
 
+use super::stats::record_action;
 use crate::consts::{CONFIG, DATABASE};
+use crate::db::ScreenshareActionKind;
+use crate::features::frozen::Frozen;
 use bridge_scrims::interaction::*;
 
 pub struct Unfreeze;
@@ -53,6 +56,7 @@ impl InteractionHandler for Unfreeze {
         let user = UserId(command.get_str("player").unwrap().parse()?);
         let res = unfreeze_user(ctx, user).await?;
         add_screensharer(command.user.id).await;
+        record_action(command.user.id, user, ScreenshareActionKind::Unfreeze).await;
         Ok(res)
     }
 
@@ -61,25 +65,31 @@ impl InteractionHandler for Unfreeze {
     }
 }
 
+/// Counts an unfreeze for the `/screensharers` leaderboard. The counter is stored as `freezes`
+/// for historical reasons, `/ssstats` counts freezes and unfreezes separately.
 pub async fn add_screensharer(sser: UserId) {
-    let _ = match DATABASE.get_screensharer(sser.0) {
-        None => DATABASE.set_screensharer(crate::db::Screensharer {
-            id: sser.0,
-            freezes: 1,
-        }),
-        Some(mut sser) => {
-            sser.freezes += 1;
-            DATABASE.set_screensharer(sser)
-        }
-    };
+    let _ = DATABASE
+        .run(move |db| match db.get_screensharer(sser.0)? {
+            None => db.set_screensharer(crate::db::Screensharer {
+                id: sser.0,
+                freezes: 1,
+            }),
+            Some(mut sser) => {
+                sser.freezes += 1;
+                db.set_screensharer(sser)
+            }
+        })
+        .await
+        .map_err(|err| tracing::error!("Failed to update screensharer stats: {}", err));
 }
 
 pub async fn unfreeze_user<'a>(ctx: &Context, user: UserId) -> InteractionResult<'a> {
     let freeze = DATABASE
-        .fetch_freezes_for(user.0)
+        .run(move |db| db.fetch_freezes_for(user.0))
+        .await?
         .ok_or_else(|| ErrorResponse::message(format!("{} is not frozen.", user.mention())))?;
 
-    let mut roles: Vec<RoleId> = freeze.roles;
+    let mut roles: Vec<RoleId> = freeze.roles.clone();
     if !roles.contains(&CONFIG.member_role) {
         roles.push(CONFIG.member_role)
     }
@@ -109,7 +119,10 @@ pub async fn unfreeze_user<'a>(ctx: &Context, user: UserId) -> InteractionResult
     member.edit(&ctx, |m| m.roles(new_roles)).await?;
 
     // Member already has their roles back so it doesn't really matter if this fails
-    let _ = DATABASE.remove_entry("Freezes", user.0);
+    let _ = DATABASE.run(move |db| db.remove_freeze(user.0)).await;
+    let _ = Frozen::schedule_cleanup(&freeze)
+        .await
+        .map_err(|err| tracing::error!("Failed to schedule freeze cleanup: {}", err));
 
     let mut response = CreateInteractionResponseData::default();
     response.content(format!(
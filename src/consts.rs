@@ -4,7 +4,9 @@ use serde::Deserialize;
 use serenity::{client::Context, model::prelude::*};
 use toml::from_str;
 
-use crate::db::Database;
+use crate::db::AsyncDatabase;
+use bridge_scrims::minecraft::{MojangResolver, ProfileResolver};
+use bridge_scrims::parse_durations::Duration;
 
 #[derive(Deserialize)]
 pub struct MemberCount(ChannelId);
@@ -37,10 +39,98 @@ pub struct Council {
     pub role: RoleId,
 }
 
+/// Ban durations for a player's 1st, 2nd, ... offense, used when staff don't pick a duration.
+/// Offenses past the end of the ladder get the last tier. A tier of `permanent` never expires.
+#[derive(Deserialize)]
+#[serde(try_from = "Vec<String>")]
+pub struct BanEscalation(Vec<Option<Duration>>);
+
+#[derive(Clone, Copy)]
+pub struct EscalationTier {
+    /// How many times the player has been banned, including this ban
+    pub offense: usize,
+    /// Starts at 1
+    pub tier: usize,
+    /// None for a permanent ban
+    pub duration: Option<Duration>,
+}
+
+impl BanEscalation {
+    pub fn tier_for(&self, offense: usize) -> EscalationTier {
+        let tier = offense.clamp(1, self.0.len());
+        EscalationTier {
+            offense,
+            tier,
+            duration: self.0[tier - 1],
+        }
+    }
+}
+
+impl Default for BanEscalation {
+    fn default() -> Self {
+        Self(vec![Some(Duration(30 * 24 * 60 * 60))])
+    }
+}
+
+impl TryFrom<Vec<String>> for BanEscalation {
+    type Error = String;
+
+    fn try_from(tiers: Vec<String>) -> Result<Self, Self::Error> {
+        if tiers.is_empty() {
+            return Err(String::from("ban_escalation needs at least one tier"));
+        }
+        tiers
+            .iter()
+            .map(|tier| match tier.as_str() {
+                "permanent" => Ok(None),
+                _ => tier
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("invalid ban_escalation tier `{}`", tier)),
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// The reduced ban players get for admitting to cheating while frozen
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct AdmissionBan(pub Duration);
+
+impl Default for AdmissionBan {
+    fn default() -> Self {
+        Self(Duration(14 * 24 * 60 * 60))
+    }
+}
+
+impl TryFrom<String> for AdmissionBan {
+    type Error = String;
+
+    fn try_from(duration: String) -> Result<Self, Self::Error> {
+        duration
+            .parse()
+            .map(Self)
+            .map_err(|_| format!("invalid admission_ban `{}`", duration))
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseBackend {
+    #[default]
+    Sqlite,
+    /// Connects to the url in the `DATABASE_URL` environment variable
+    Postgres,
+}
+
 #[derive(Deserialize)]
 pub struct Config {
     pub guild: GuildId,
 
+    #[serde(default)]
+    pub database: DatabaseBackend,
+
     pub rank_queue_categories: Vec<Vec<ChannelId>>,
 
     pub member_role: RoleId,
@@ -56,10 +146,26 @@ pub struct Config {
     pub trial_support: RoleId,
     pub support_bans: ChannelId,
     pub screenshare_requests: ChannelId,
+    /// How many screenshare tickets one member can have open at once
+    #[serde(default = "default_max_screenshare_requests")]
+    pub max_screenshare_requests: usize,
     pub muted_role: RoleId,
     pub frozen: RoleId,
     pub frozen_chat: ChannelId,
     pub hello_cheaters: ChannelId,
+    /// Where every freeze gets its own text and voice channel, outside of any category if unset
+    pub freeze_category: Option<ChannelId>,
+    /// Minutes frozen users have to admit or join their voice channel before screensharers are pinged
+    #[serde(default = "default_freeze_deadline")]
+    pub freeze_deadline: u64,
+    /// Bans frozen users for refusing to screenshare once the deadline passes
+    #[serde(default)]
+    pub freeze_deadline_ban: bool,
+    /// Bans users that leave the server while frozen
+    #[serde(default = "default_freeze_leave_ban")]
+    pub freeze_leave_ban: bool,
+    #[serde(default)]
+    pub admission_ban: AdmissionBan,
     pub ss_logs: ChannelId,
     pub freeze_emoji: String,
     pub unfreeze_emoji: String,
@@ -72,8 +178,18 @@ pub struct Config {
     pub reaction_logs: ChannelId,
     pub color_roles: Vec<RoleId>,
 
+    #[serde(default)]
+    pub ban_escalation: BanEscalation,
     pub appeal_channel: ChannelId,
     pub appeal_forum: String,
+    /// Where appeals sent through the bot are posted for staff to decide on
+    pub appeal_reviews: ChannelId,
+
+    /// Where joins that look like alts of banned users are reported
+    pub evasion_alerts: ChannelId,
+    /// The score (out of 100) a join needs to be reported
+    #[serde(default = "default_evasion_threshold")]
+    pub evasion_threshold: u32,
 
     pub prefabs: HashMap<String, String>,
 
@@ -90,20 +206,121 @@ pub struct Config {
     pub expanding_max: usize,
 }
 
+impl Config {
+    /// Catches channels that were set to one meant for something else, like staff-only channels
+    /// everyone can read or freezes ending up between the tickets
+    fn validate(&self) -> Result<(), String> {
+        let separate = [
+            (
+                "appeal_reviews",
+                Some(self.appeal_reviews),
+                "appeal_channel",
+                self.appeal_channel,
+            ),
+            (
+                "evasion_alerts",
+                Some(self.evasion_alerts),
+                "appeal_channel",
+                self.appeal_channel,
+            ),
+            (
+                "freeze_category",
+                self.freeze_category,
+                "screenshare_requests",
+                self.screenshare_requests,
+            ),
+        ];
+        for (name, channel, other, other_channel) in separate {
+            if channel == Some(other_channel) {
+                return Err(format!("{} can't be the same channel as {}", name, other));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_evasion_threshold() -> u32 {
+    50
+}
+
+fn default_freeze_deadline() -> u64 {
+    5
+}
+
+fn default_freeze_leave_ban() -> bool {
+    true
+}
+
+fn default_max_screenshare_requests() -> usize {
+    1
+}
+
 pub struct Secrets {
     pub bot_token: String,
 }
 
 lazy_static::lazy_static! {
     pub static ref DATABASE_PATH: PathBuf = std::env::current_dir().unwrap();
-    pub static ref DATABASE: Database = Database::init();
+    pub static ref DATABASE: AsyncDatabase = crate::db::init(CONFIG.database);
 
     pub static ref CONFIG: Config = {
         let config_string: String = fs::read_to_string("Config.toml").expect("Config Not Supplied!");
-        from_str(&config_string).expect("Config could not be parsed!")
+        let config: Config = from_str(&config_string).expect("Config could not be parsed!");
+        if let Err(err) = config.validate() {
+            panic!("Config is invalid: {}", err);
+        }
+        config
     };
 
+    pub static ref MINECRAFT: Box<dyn ProfileResolver> = Box::new(MojangResolver::new());
+
     pub static ref SECRETS: Secrets = Secrets {
         bot_token: env::var("BOT_TOKEN").unwrap(),
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn escalation_tiers() {
+        let ladder = BanEscalation::try_from(vec![
+            String::from("7d"),
+            String::from("30d"),
+            String::from("permanent"),
+        ])
+        .unwrap();
+        let tiers = (1..=4)
+            .map(|offense| ladder.tier_for(offense))
+            .map(|tier| (tier.tier, tier.duration.map(|d| d.0)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tiers,
+            vec![
+                (1, Some(7 * DAY)),
+                (2, Some(30 * DAY)),
+                (3, None),
+                (3, None)
+            ]
+        );
+        assert_eq!(
+            BanEscalation::default().tier_for(5).duration.map(|d| d.0),
+            Some(30 * DAY)
+        );
+    }
+
+    #[test]
+    fn shipped_config_is_valid() {
+        let config: Config = from_str(&fs::read_to_string("Config.toml").unwrap()).unwrap();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn invalid_escalation() {
+        assert!(BanEscalation::try_from(Vec::new()).is_err());
+        assert!(BanEscalation::try_from(vec![String::from("forever-ish")]).is_err());
+    }
+}
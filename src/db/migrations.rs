@@ -0,0 +1,676 @@
+//! Versioned schema migrations for every database backend.
+//!
+//! Every change to the schema is appended to [`MIGRATIONS`] with the next version number,
+//! written once for each backend. Migrations that were already shipped must never be edited,
+//! since databases in production will not run them again.
+
+use std::fmt::Display;
+
+use sqlite::Connection;
+
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sqlite: &'static str,
+    pub postgres: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        // `if not exists` since databases created before versioning already have these tables
+        sqlite: "
+        create table if not exists ScheduledScrimUnbans (
+            id integer primary key,
+            time integer,
+            roles text
+        );
+        create table if not exists Notes (
+            userid integer,
+            id integer,
+            created_at integer,
+            note text,
+            creator integer
+        );
+        create table if not exists Reaction (
+            user integer primary key,
+            emoji text,
+            trigger text
+        );
+        create table if not exists Screenshares (
+            id integer primary key,
+            creator integer,
+            in_question integer
+        );
+        create table if not exists Freezes (
+            id integer,
+            roles text,
+            time integer
+        );
+        create table if not exists ScreensharerStats (
+            id integer primary key,
+            freezes integer
+        );
+    ",
+        postgres: "
+        create table ScheduledScrimUnbans (
+            id bigint primary key,
+            time bigint,
+            roles text not null
+        );
+        create table Notes (
+            userid bigint not null,
+            id bigint not null,
+            created_at bigint not null,
+            note text not null,
+            creator bigint not null
+        );
+        create table Reaction (
+            \"user\" bigint primary key,
+            emoji text not null,
+            trigger text not null
+        );
+        create table Screenshares (
+            id bigint primary key,
+            creator bigint not null,
+            in_question bigint not null
+        );
+        create table Freezes (
+            id bigint not null,
+            roles text not null,
+            time bigint not null
+        );
+        create table ScreensharerStats (
+            id bigint primary key,
+            freezes bigint not null
+        );
+    ",
+    },
+    Migration {
+        version: 2,
+        description: "scrim ban history",
+        // Bans that were active before the history existed are kept without staff or issue date
+        sqlite: "
+            create table ScrimBans (
+                id integer primary key autoincrement,
+                user integer not null,
+                staff integer,
+                reason text not null,
+                issued_at integer,
+                expires_at integer,
+                revoked_by integer,
+                revoke_reason text,
+                revoked_at integer
+            );
+            create index ScrimBansByUser on ScrimBans (user);
+            insert into ScrimBans (user, reason, expires_at)
+                select id, 'Issued before ban history was recorded', time
+                from ScheduledScrimUnbans where time is not null;
+        ",
+        postgres: "
+            create table ScrimBans (
+                id bigserial primary key,
+                \"user\" bigint not null,
+                staff bigint,
+                reason text not null,
+                issued_at bigint,
+                expires_at bigint,
+                revoked_by bigint,
+                revoke_reason text,
+                revoked_at bigint
+            );
+            create index ScrimBansByUser on ScrimBans (\"user\");
+            insert into ScrimBans (\"user\", reason, expires_at)
+                select id, 'Issued before ban history was recorded', time
+                from ScheduledScrimUnbans where time is not null;
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "permanent scrim bans",
+        // A missing unban time used to mean the unban was already logged, now it means permanent
+        sqlite: "
+            alter table ScheduledScrimUnbans add column logged integer not null default 0;
+            update ScheduledScrimUnbans set logged = 1 where time is null;
+        ",
+        postgres: "
+            alter table ScheduledScrimUnbans add column logged boolean not null default false;
+            update ScheduledScrimUnbans set logged = true where time is null;
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "ban appeals",
+        sqlite: "
+            create table Appeals (
+                id integer primary key autoincrement,
+                ban integer not null unique,
+                user integer not null,
+                appeal text not null,
+                created_at integer not null,
+                status text not null default 'pending',
+                reviewed_by integer,
+                reviewed_at integer
+            );
+        ",
+        postgres: "
+            create table Appeals (
+                id bigserial primary key,
+                ban bigint not null unique,
+                \"user\" bigint not null,
+                appeal text not null,
+                created_at bigint not null,
+                status text not null default 'pending',
+                reviewed_by bigint,
+                reviewed_at bigint
+            );
+        ",
+    },
+    Migration {
+        version: 5,
+        description: "scrim ban edits",
+        sqlite: "
+            create table ScrimBanEdits (
+                id integer primary key autoincrement,
+                ban integer not null,
+                staff integer not null,
+                edited_at integer not null,
+                old_expires_at integer,
+                new_expires_at integer,
+                old_reason text not null,
+                new_reason text not null
+            );
+            create index ScrimBanEditsByBan on ScrimBanEdits (ban);
+        ",
+        postgres: "
+            create table ScrimBanEdits (
+                id bigserial primary key,
+                ban bigint not null,
+                staff bigint not null,
+                edited_at bigint not null,
+                old_expires_at bigint,
+                new_expires_at bigint,
+                old_reason text not null,
+                new_reason text not null
+            );
+            create index ScrimBanEditsByBan on ScrimBanEdits (ban);
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "known accounts",
+        sqlite: "
+            create table KnownAccounts (
+                id integer primary key,
+                name text not null,
+                avatar text,
+                invite text
+            );
+        ",
+        postgres: "
+            create table KnownAccounts (
+                id bigint primary key,
+                name text not null,
+                avatar text,
+                invite text
+            );
+        ",
+    },
+    Migration {
+        version: 7,
+        description: "minecraft accounts",
+        // Minecraft names are case insensitive, so every user can have each name once
+        sqlite: "
+            create table MinecraftAccounts (
+                user integer not null,
+                ign text not null,
+                linked_by integer not null,
+                linked_at integer not null
+            );
+            create unique index MinecraftAccountsByUser on MinecraftAccounts (user, lower(ign));
+            create index MinecraftAccountsByIgn on MinecraftAccounts (lower(ign));
+        ",
+        postgres: "
+            create table MinecraftAccounts (
+                \"user\" bigint not null,
+                ign text not null,
+                linked_by bigint not null,
+                linked_at bigint not null
+            );
+            create unique index MinecraftAccountsByUser on MinecraftAccounts (\"user\", lower(ign));
+            create index MinecraftAccountsByIgn on MinecraftAccounts (lower(ign));
+        ",
+    },
+    Migration {
+        version: 8,
+        description: "minecraft account uuids",
+        sqlite: "
+            alter table MinecraftAccounts add column uuid text;
+            create index MinecraftAccountsByUuid on MinecraftAccounts (uuid);
+        ",
+        postgres: "
+            alter table MinecraftAccounts add column uuid text;
+            create index MinecraftAccountsByUuid on MinecraftAccounts (uuid);
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "screenshare creation time",
+        // Open tickets get a fresh timeout, since when they were opened isn't known
+        sqlite: "
+            alter table Screenshares add column created_at integer not null default 0;
+            update Screenshares set created_at = cast(strftime('%s', 'now') as integer);
+        ",
+        postgres: "
+            alter table Screenshares add column created_at bigint not null default 0;
+            update Screenshares set created_at = extract(epoch from now())::bigint;
+        ",
+    },
+    Migration {
+        version: 10,
+        description: "scheduled jobs",
+        sqlite: "
+            create table ScheduledJobs (
+                id integer primary key autoincrement,
+                key text unique,
+                kind text not null,
+                payload text not null,
+                run_at integer not null,
+                attempts integer not null default 0,
+                last_error text,
+                locked_until integer,
+                failed integer not null default 0
+            );
+            create index ScheduledJobsByRunAt on ScheduledJobs (run_at);
+        ",
+        postgres: "
+            create table ScheduledJobs (
+                id bigserial primary key,
+                key text unique,
+                kind text not null,
+                payload text not null,
+                run_at bigint not null,
+                attempts bigint not null default 0,
+                last_error text,
+                locked_until bigint,
+                failed boolean not null default false
+            );
+            create index ScheduledJobsByRunAt on ScheduledJobs (run_at);
+        ",
+    },
+    Migration {
+        version: 11,
+        description: "screenshare history",
+        sqlite: "
+            create table ScreenshareHistory (
+                channel integer primary key,
+                creator integer not null,
+                in_question integer not null,
+                closer integer not null,
+                outcome text not null,
+                notes text,
+                created_at integer not null,
+                closed_at integer not null
+            );
+            create index ScreenshareHistoryByUser on ScreenshareHistory (in_question);
+            create index ScreenshareHistoryByClose on ScreenshareHistory (closed_at);
+        ",
+        postgres: "
+            create table ScreenshareHistory (
+                channel bigint primary key,
+                creator bigint not null,
+                in_question bigint not null,
+                closer bigint not null,
+                outcome text not null,
+                notes text,
+                created_at bigint not null,
+                closed_at bigint not null
+            );
+            create index ScreenshareHistoryByUser on ScreenshareHistory (in_question);
+            create index ScreenshareHistoryByClose on ScreenshareHistory (closed_at);
+        ",
+    },
+    Migration {
+        version: 12,
+        description: "screenshare actions",
+        sqlite: "
+            create table ScreenshareActions (
+                id integer primary key autoincrement,
+                staff integer not null,
+                user integer not null,
+                kind text not null,
+                at integer not null,
+                response integer
+            );
+            create index ScreenshareActionsByTime on ScreenshareActions (at);
+        ",
+        postgres: "
+            create table ScreenshareActions (
+                id bigserial primary key,
+                staff bigint not null,
+                \"user\" bigint not null,
+                kind text not null,
+                at bigint not null,
+                response bigint
+            );
+            create index ScreenshareActionsByTime on ScreenshareActions (at);
+        ",
+    },
+    Migration {
+        version: 13,
+        description: "freeze admissions",
+        sqlite: "
+            alter table Freezes add column admitted_at integer;
+        ",
+        postgres: "
+            alter table Freezes add column admitted_at bigint;
+        ",
+    },
+    Migration {
+        version: 14,
+        description: "freeze channels",
+        sqlite: "
+            alter table Freezes add column text_channel integer;
+            alter table Freezes add column voice_channel integer;
+        ",
+        postgres: "
+            alter table Freezes add column text_channel bigint, add column voice_channel bigint;
+        ",
+    },
+    Migration {
+        version: 15,
+        description: "screenshare assignees",
+        sqlite: "
+            alter table Screenshares add column assignee integer;
+            alter table ScreenshareHistory add column assignee integer;
+        ",
+        postgres: "
+            alter table Screenshares add column assignee bigint;
+            alter table ScreenshareHistory add column assignee bigint;
+        ",
+    },
+    Migration {
+        version: 16,
+        description: "screenshare reporters",
+        sqlite: "
+            create table ScreenshareReporters (
+                channel integer not null,
+                user integer not null,
+                added_at integer not null,
+                primary key (channel, user)
+            );
+        ",
+        postgres: "
+            create table ScreenshareReporters (
+                channel bigint not null,
+                \"user\" bigint not null,
+                added_at bigint not null,
+                primary key (channel, \"user\")
+            );
+        ",
+    },
+];
+
+const SCHEMA_VERSION_TABLE: &str = "
+    create table if not exists schema_version (
+        version bigint primary key,
+        description text,
+        applied_at bigint
+    )
+";
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Sqlite(sqlite::Error),
+    Postgres(postgres::Error),
+    /// The database was last migrated by a newer version of the bot
+    UnknownVersion {
+        found: i64,
+        latest: i64,
+    },
+}
+
+impl Display for MigrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sqlite(err) => write!(f, "{}", err),
+            Self::Postgres(err) => write!(f, "{}", err),
+            Self::UnknownVersion { found, latest } => write!(
+                f,
+                "database schema is at version {} but the newest known version is {}",
+                found, latest
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<sqlite::Error> for MigrationError {
+    fn from(err: sqlite::Error) -> Self {
+        Self::Sqlite(err)
+    }
+}
+
+impl From<postgres::Error> for MigrationError {
+    fn from(err: postgres::Error) -> Self {
+        Self::Postgres(err)
+    }
+}
+
+fn pending(found: i64, migrations: &[Migration]) -> Result<&[Migration], MigrationError> {
+    let latest = migrations.last().map_or(0, |m| m.version);
+    if found > latest {
+        return Err(MigrationError::UnknownVersion { found, latest });
+    }
+    Ok(&migrations[migrations.partition_point(|m| m.version <= found)..])
+}
+
+fn log_applied(migration: &Migration) {
+    tracing::info!(
+        "Migrated database to version {} ({})",
+        migration.version,
+        migration.description
+    );
+}
+
+pub fn sqlite_version(conn: &Connection) -> Result<i64, sqlite::Error> {
+    conn.execute(SCHEMA_VERSION_TABLE)?;
+
+    let mut stmt = conn.prepare("SELECT max(version) FROM schema_version")?;
+    stmt.next()?;
+    Ok(stmt.read::<Option<i64>>(0)?.unwrap_or_default())
+}
+
+/// Applies every pending migration, each in its own transaction.
+/// Returns the version the database is at afterwards.
+pub fn migrate_sqlite(conn: &Connection) -> Result<i64, MigrationError> {
+    run_sqlite(conn, MIGRATIONS)
+}
+
+fn run_sqlite(conn: &Connection, migrations: &[Migration]) -> Result<i64, MigrationError> {
+    let mut version = sqlite_version(conn)?;
+    for migration in pending(version, migrations)? {
+        conn.execute("BEGIN")?;
+        if let Err(err) = apply_sqlite(conn, migration) {
+            let _ = conn.execute("ROLLBACK");
+            return Err(err.into());
+        }
+        conn.execute("COMMIT")?;
+        log_applied(migration);
+        version = migration.version;
+    }
+    Ok(version)
+}
+
+fn apply_sqlite(conn: &Connection, migration: &Migration) -> Result<(), sqlite::Error> {
+    conn.execute(migration.sqlite)?;
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO schema_version (version, description, applied_at) values (?, ?, strftime('%s', 'now'))",
+    )?;
+    stmt.bind(1, migration.version)?;
+    stmt.bind(2, migration.description)?;
+    stmt.next()?;
+    Ok(())
+}
+
+pub fn postgres_version(client: &mut postgres::Client) -> Result<i64, postgres::Error> {
+    client.batch_execute(SCHEMA_VERSION_TABLE)?;
+
+    let row = client.query_one("SELECT max(version) FROM schema_version", &[])?;
+    Ok(row.get::<_, Option<i64>>(0).unwrap_or_default())
+}
+
+/// Applies every pending migration, each in its own transaction.
+/// Returns the version the database is at afterwards.
+pub fn migrate_postgres(client: &mut postgres::Client) -> Result<i64, MigrationError> {
+    let mut version = postgres_version(client)?;
+    for migration in pending(version, MIGRATIONS)? {
+        // The transaction is rolled back when dropped without committing
+        let mut transaction = client.transaction()?;
+        transaction.batch_execute(migration.postgres)?;
+        transaction.execute(
+            "INSERT INTO schema_version (version, description, applied_at) values ($1, $2, extract(epoch from now())::bigint)",
+            &[&migration.version, &migration.description],
+        )?;
+        transaction.commit()?;
+        log_applied(migration);
+        version = migration.version;
+    }
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The schema as it was created before migrations were introduced
+    const UNVERSIONED_FIXTURE: &str = "
+        create table ScheduledScrimUnbans (id integer primary key, time integer, roles text);
+        create table Notes (userid integer, id integer, created_at integer, note text, creator integer);
+        create table Reaction (user integer primary key, emoji text, trigger text);
+        create table Screenshares (id integer primary key, creator integer, in_question integer);
+        create table Freezes (id integer, roles text, time integer);
+        create table ScreensharerStats (id integer primary key, freezes integer);
+
+        insert into ScheduledScrimUnbans values (1, 1700000000, '2,3');
+        insert into ScheduledScrimUnbans values (4, NULL, '');
+        insert into Notes values (5, 1, 1700000000, 'hello', 6);
+        insert into Screenshares values (7, 8, 9);
+    ";
+
+    fn latest_version() -> i64 {
+        MIGRATIONS.last().unwrap().version
+    }
+
+    fn count(conn: &Connection, query: &str) -> i64 {
+        let mut stmt = conn.prepare(query).unwrap();
+        stmt.next().unwrap();
+        stmt.read::<i64>(0).unwrap()
+    }
+
+    #[test]
+    fn versions_are_ordered() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i64 + 1);
+        }
+    }
+
+    #[test]
+    fn migrate_empty_database() {
+        let conn = Connection::open(":memory:").unwrap();
+        assert_eq!(migrate_sqlite(&conn).unwrap(), latest_version());
+        assert_eq!(sqlite_version(&conn).unwrap(), latest_version());
+        assert_eq!(
+            count(&conn, "select count(*) from schema_version"),
+            MIGRATIONS.len() as i64
+        );
+    }
+
+    #[test]
+    fn migrate_unversioned_fixture() {
+        let conn = Connection::open(":memory:").unwrap();
+        conn.execute(UNVERSIONED_FIXTURE).unwrap();
+        assert_eq!(sqlite_version(&conn).unwrap(), 0);
+
+        assert_eq!(migrate_sqlite(&conn).unwrap(), latest_version());
+        assert_eq!(sqlite_version(&conn).unwrap(), latest_version());
+        assert_eq!(count(&conn, "select count(*) from ScheduledScrimUnbans"), 2);
+        assert_eq!(count(&conn, "select count(*) from Notes"), 1);
+        // Only the ban that was still running is carried over into the history
+        assert_eq!(count(&conn, "select count(*) from ScrimBans"), 1);
+        assert_eq!(
+            count(
+                &conn,
+                "select count(*) from ScheduledScrimUnbans where logged = 1 and id = 4"
+            ),
+            1
+        );
+        // Open tickets are timed from the migration on
+        assert_eq!(
+            count(
+                &conn,
+                "select count(*) from Screenshares where created_at > 1700000000"
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn migrate_is_idempotent() {
+        let conn = Connection::open(":memory:").unwrap();
+        migrate_sqlite(&conn).unwrap();
+        migrate_sqlite(&conn).unwrap();
+        assert_eq!(
+            count(&conn, "select count(*) from schema_version"),
+            MIGRATIONS.len() as i64
+        );
+    }
+
+    #[test]
+    fn refuse_newer_schema() {
+        let conn = Connection::open(":memory:").unwrap();
+        migrate_sqlite(&conn).unwrap();
+        conn.execute(format!(
+            "insert into schema_version (version) values ({})",
+            latest_version() + 1
+        ))
+        .unwrap();
+
+        assert!(matches!(
+            migrate_sqlite(&conn),
+            Err(MigrationError::UnknownVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn failed_migration_rolls_back() {
+        let conn = Connection::open(":memory:").unwrap();
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "working",
+                sqlite: "create table Working (id integer);",
+                postgres: "",
+            },
+            Migration {
+                version: 2,
+                description: "broken",
+                sqlite: "create table Broken (id integer); not valid sql;",
+                postgres: "",
+            },
+        ];
+
+        assert!(run_sqlite(&conn, &migrations).is_err());
+        assert_eq!(sqlite_version(&conn).unwrap(), 1);
+        assert_eq!(
+            count(
+                &conn,
+                "select count(*) from sqlite_master where name = 'Broken'"
+            ),
+            0
+        );
+    }
+}
@@ -0,0 +1,883 @@
+use std::sync::Mutex;
+
+use postgres::{Client, NoTls};
+use time::OffsetDateTime;
+
+use super::{
+    migrations,
+    row::{decode, FromRow},
+    Database, Result,
+};
+use crate::model::*;
+
+pub struct PostgresDatabase {
+    pub client: Mutex<Client>,
+}
+
+impl PostgresDatabase {
+    pub fn connect(url: &str) -> Result<Self> {
+        let client = tokio::task::block_in_place(|| Client::connect(url, NoTls))?;
+        Self::new(client)
+    }
+
+    pub fn new(mut client: Client) -> Result<Self> {
+        tokio::task::block_in_place(|| migrations::migrate_postgres(&mut client))?;
+        Ok(Self {
+            client: Mutex::new(client),
+        })
+    }
+
+    /// Queries reach this through [`super::AsyncDatabase`], which already runs them on a
+    /// blocking thread where the client is free to drive its own runtime
+    fn with_client<T, F>(&self, predicate: F) -> Result<T>
+    where
+        F: FnOnce(&mut Client) -> std::result::Result<T, postgres::Error>,
+    {
+        let mut client = self.client.lock().unwrap_or_else(|err| err.into_inner());
+        Ok(predicate(&mut client)?)
+    }
+}
+
+/// Decodes every row, malformed rows are logged and skipped
+fn decode_all<T: FromRow>(rows: Vec<postgres::Row>) -> Vec<T> {
+    rows.iter().filter_map(decode).collect()
+}
+
+impl Database for PostgresDatabase {
+    fn fetch_scrim_unbans(&self) -> Result<Vec<ScrimUnban>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT id, time, roles, logged FROM ScheduledScrimUnbans",
+                &[],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn add_scrim_unban(&self, id: u64, unban_date: Option<OffsetDateTime>, roles: &Ids) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "INSERT INTO ScheduledScrimUnbans (id, time, roles) values ($1, $2, $3)",
+                &[
+                    &(id as i64),
+                    &unban_date.map(|d| d.unix_timestamp()),
+                    &roles.to_string(),
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn modify_scrim_unban(
+        &self,
+        id: u64,
+        unban_date: Option<OffsetDateTime>,
+        roles: &Ids,
+    ) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "UPDATE ScheduledScrimUnbans SET time = $1, roles = $2, logged = false WHERE id = $3",
+                &[
+                    &unban_date.map(|d| d.unix_timestamp()),
+                    &roles.to_string(),
+                    &(id as i64),
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn mark_scrim_unban_logged(&self, id: u64) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "UPDATE ScheduledScrimUnbans SET logged = true WHERE id = $1",
+                &[&(id as i64)],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn remove_scrim_unban(&self, id: u64) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "DELETE FROM ScheduledScrimUnbans WHERE id = $1",
+                &[&(id as i64)],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn add_scrim_ban(
+        &self,
+        user: u64,
+        staff: u64,
+        reason: &str,
+        issued_at: OffsetDateTime,
+        expires_at: Option<OffsetDateTime>,
+    ) -> Result<i64> {
+        let row = self.with_client(|client| {
+            client.query_one(
+                "INSERT INTO ScrimBans (\"user\", staff, reason, issued_at, expires_at) \
+                values ($1, $2, $3, $4, $5) RETURNING id",
+                &[
+                    &(user as i64),
+                    &(staff as i64),
+                    &reason,
+                    &issued_at.unix_timestamp(),
+                    &expires_at.map(|d| d.unix_timestamp()),
+                ],
+            )
+        })?;
+        Ok(row.get(0))
+    }
+
+    fn revoke_scrim_bans(
+        &self,
+        user: u64,
+        revoked_by: Option<u64>,
+        reason: &str,
+        revoked_at: OffsetDateTime,
+    ) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "UPDATE ScrimBans SET revoked_by = $1, revoke_reason = $2, revoked_at = $3 \
+                WHERE \"user\" = $4 AND revoked_at IS NULL",
+                &[
+                    &revoked_by.map(|id| id as i64),
+                    &reason,
+                    &revoked_at.unix_timestamp(),
+                    &(user as i64),
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn fetch_scrim_ban_history(&self, user: u64) -> Result<Vec<ScrimBan>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT id, \"user\", staff, reason, issued_at, expires_at, revoked_by, revoke_reason, revoked_at \
+                FROM ScrimBans WHERE \"user\" = $1 ORDER BY id DESC",
+                &[&(user as i64)],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn fetch_scrim_ban(&self, id: u64) -> Result<Option<ScrimBan>> {
+        let row = self.with_client(|client| {
+            client.query_opt(
+                "SELECT id, \"user\", staff, reason, issued_at, expires_at, revoked_by, revoke_reason, revoked_at \
+                FROM ScrimBans WHERE id = $1",
+                &[&(id as i64)],
+            )
+        })?;
+        Ok(row.as_ref().and_then(decode))
+    }
+
+    fn edit_scrim_ban(
+        &self,
+        ban: u64,
+        staff: u64,
+        edited_at: OffsetDateTime,
+        expires_at: Option<OffsetDateTime>,
+        reason: &str,
+    ) -> Result {
+        let expires_at = expires_at.map(|d| d.unix_timestamp());
+        self.with_client(|client| {
+            let mut transaction = client.transaction()?;
+            transaction.execute(
+                "INSERT INTO ScrimBanEdits \
+                (ban, staff, edited_at, old_expires_at, new_expires_at, old_reason, new_reason) \
+                SELECT id, $1, $2, expires_at, $3, reason, $4 FROM ScrimBans WHERE id = $5",
+                &[
+                    &(staff as i64),
+                    &edited_at.unix_timestamp(),
+                    &expires_at,
+                    &reason,
+                    &(ban as i64),
+                ],
+            )?;
+            transaction.execute(
+                "UPDATE ScrimBans SET expires_at = $1, reason = $2 WHERE id = $3",
+                &[&expires_at, &reason, &(ban as i64)],
+            )?;
+            transaction.commit()
+        })
+    }
+
+    fn fetch_scrim_ban_edits(&self, ban: u64) -> Result<Vec<ScrimBanEdit>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT staff, edited_at, old_expires_at, new_expires_at, old_reason, new_reason \
+                FROM ScrimBanEdits WHERE ban = $1 ORDER BY id",
+                &[&(ban as i64)],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn add_appeal(
+        &self,
+        ban: u64,
+        user: u64,
+        appeal: &str,
+        created_at: OffsetDateTime,
+    ) -> Result<i64> {
+        let row = self.with_client(|client| {
+            client.query_one(
+                "INSERT INTO Appeals (ban, \"user\", appeal, created_at) values ($1, $2, $3, $4) RETURNING id",
+                &[
+                    &(ban as i64),
+                    &(user as i64),
+                    &appeal,
+                    &created_at.unix_timestamp(),
+                ],
+            )
+        })?;
+        Ok(row.get(0))
+    }
+
+    fn fetch_appeal(&self, id: u64) -> Result<Option<Appeal>> {
+        let row = self.with_client(|client| {
+            client.query_opt(
+                "SELECT id, ban, \"user\", appeal, created_at, status, reviewed_by, reviewed_at \
+                FROM Appeals WHERE id = $1",
+                &[&(id as i64)],
+            )
+        })?;
+        Ok(row.as_ref().and_then(decode))
+    }
+
+    fn fetch_appeal_for_ban(&self, ban: u64) -> Result<Option<Appeal>> {
+        let row = self.with_client(|client| {
+            client.query_opt(
+                "SELECT id, ban, \"user\", appeal, created_at, status, reviewed_by, reviewed_at \
+                FROM Appeals WHERE ban = $1",
+                &[&(ban as i64)],
+            )
+        })?;
+        Ok(row.as_ref().and_then(decode))
+    }
+
+    fn fetch_appeals_for_user(&self, user: u64) -> Result<Vec<Appeal>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT id, ban, \"user\", appeal, created_at, status, reviewed_by, reviewed_at \
+                FROM Appeals WHERE \"user\" = $1",
+                &[&(user as i64)],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn decide_appeal(
+        &self,
+        id: u64,
+        status: AppealStatus,
+        reviewed_by: u64,
+        reviewed_at: OffsetDateTime,
+    ) -> Result<bool> {
+        let updated = self.with_client(|client| {
+            client.execute(
+                "UPDATE Appeals SET status = $1, reviewed_by = $2, reviewed_at = $3 \
+                WHERE id = $4 AND status = 'pending'",
+                &[
+                    &status.as_str(),
+                    &(reviewed_by as i64),
+                    &reviewed_at.unix_timestamp(),
+                    &(id as i64),
+                ],
+            )
+        })?;
+        Ok(updated > 0)
+    }
+
+    fn reopen_appeal(&self, id: u64) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "UPDATE Appeals SET status = 'pending', reviewed_by = NULL, reviewed_at = NULL \
+                WHERE id = $1",
+                &[&(id as i64)],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn fetch_custom_reactions(&self) -> Result<Vec<CustomReaction>> {
+        let rows = self.with_client(|client| {
+            client.query("SELECT \"user\", emoji, trigger FROM Reaction", &[])
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn fetch_custom_reactions_for(&self, user: u64) -> Result<Vec<CustomReaction>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT \"user\", emoji, trigger FROM Reaction WHERE \"user\" = $1",
+                &[&(user as i64)],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn fetch_custom_reactions_with_trigger(&self, trigger: &str) -> Result<Vec<CustomReaction>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT \"user\", emoji, trigger FROM Reaction WHERE trigger = $1",
+                &[&trigger],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn add_custom_reaction(&self, user: u64, emoji: &str, trigger: &str) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "INSERT INTO Reaction (\"user\", emoji, trigger) values ($1, $2, $3)",
+                &[&(user as i64), &emoji, &trigger],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn remove_custom_reaction(&self, user: u64) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "DELETE FROM Reaction WHERE \"user\" = $1",
+                &[&(user as i64)],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn fetch_notes_for(&self, userid: u64) -> Result<Vec<Note>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT userid, id, created_at, note, creator FROM Notes WHERE userid = $1 ORDER BY id",
+                &[&(userid as i64)],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn add_note(
+        &self,
+        userid: u64,
+        created_at: OffsetDateTime,
+        note: &str,
+        creator: u64,
+    ) -> Result<i64> {
+        self.with_client(|client| {
+            let mut transaction = client.transaction()?;
+            let count: i64 = transaction
+                .query_one(
+                    "SELECT count(*) FROM Notes WHERE userid = $1",
+                    &[&(userid as i64)],
+                )?
+                .get(0);
+            transaction.execute(
+                "INSERT INTO Notes (userid, id, created_at, note, creator) values ($1, $2, $3, $4, $5)",
+                &[
+                    &(userid as i64),
+                    &(count + 1),
+                    &created_at.unix_timestamp(),
+                    &note,
+                    &(creator as i64),
+                ],
+            )?;
+            transaction.commit()?;
+            Ok(count + 1)
+        })
+    }
+
+    fn remove_note(&self, userid: u64, id: u64) -> Result {
+        self.with_client(|client| {
+            let mut transaction = client.transaction()?;
+            transaction.execute(
+                "DELETE FROM Notes WHERE userid = $1 AND id = $2",
+                &[&(userid as i64), &(id as i64)],
+            )?;
+            transaction.execute(
+                "UPDATE Notes SET id = id - 1 WHERE userid = $1 AND id >= $2",
+                &[&(userid as i64), &(id as i64)],
+            )?;
+            transaction.commit()
+        })
+    }
+
+    fn fetch_screenshares_for(&self, id: u64) -> Result<Option<Screenshare>> {
+        let row = self.with_client(|client| {
+            client.query_opt(
+                "SELECT id, creator, in_question, created_at, assignee FROM Screenshares \
+                WHERE id = $1",
+                &[&(id as i64)],
+            )
+        })?;
+        Ok(row.as_ref().and_then(decode))
+    }
+
+    fn count_screenshares(&self) -> Result<i64> {
+        let row =
+            self.with_client(|client| client.query_one("SELECT count(*) FROM Screenshares", &[]))?;
+        Ok(row.get(0))
+    }
+
+    fn fetch_screenshares(&self) -> Result<Vec<Screenshare>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT id, creator, in_question, created_at, assignee FROM Screenshares",
+                &[],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn add_screenshare(
+        &self,
+        id: u64,
+        creator: u64,
+        in_question: u64,
+        created_at: OffsetDateTime,
+    ) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "INSERT INTO Screenshares (id, creator, in_question, created_at) \
+                values ($1, $2, $3, $4)",
+                &[
+                    &(id as i64),
+                    &(creator as i64),
+                    &(in_question as i64),
+                    &created_at.unix_timestamp(),
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn remove_screenshare(&self, id: u64) -> Result {
+        self.with_client(|client| {
+            client.execute("DELETE FROM Screenshares WHERE id = $1", &[&(id as i64)])
+        })?;
+        Ok(())
+    }
+
+    fn claim_screenshare(&self, id: u64, assignee: u64) -> Result<bool> {
+        let updated = self.with_client(|client| {
+            client.execute(
+                "UPDATE Screenshares SET assignee = $1 WHERE id = $2 AND assignee IS NULL",
+                &[&(assignee as i64), &(id as i64)],
+            )
+        })?;
+        Ok(updated > 0)
+    }
+
+    fn add_screenshare_reporter(
+        &self,
+        channel: u64,
+        user: u64,
+        added_at: OffsetDateTime,
+    ) -> Result<bool> {
+        let inserted = self.with_client(|client| {
+            client.execute(
+                "INSERT INTO ScreenshareReporters (channel, \"user\", added_at) \
+                values ($1, $2, $3) ON CONFLICT DO NOTHING",
+                &[
+                    &(channel as i64),
+                    &(user as i64),
+                    &added_at.unix_timestamp(),
+                ],
+            )
+        })?;
+        Ok(inserted > 0)
+    }
+
+    fn fetch_screenshare_reporters(&self, channel: u64) -> Result<Vec<ScreenshareReporter>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT \"user\", added_at FROM ScreenshareReporters WHERE channel = $1 \
+                ORDER BY added_at, \"user\"",
+                &[&(channel as i64)],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn assign_screenshare(&self, id: u64, assignee: Option<u64>) -> Result<bool> {
+        let updated = self.with_client(|client| {
+            client.execute(
+                "UPDATE Screenshares SET assignee = $1 WHERE id = $2",
+                &[&assignee.map(|assignee| assignee as i64), &(id as i64)],
+            )
+        })?;
+        Ok(updated > 0)
+    }
+
+    fn close_screenshare(
+        &self,
+        id: u64,
+        closer: u64,
+        outcome: ScreenshareOutcome,
+        notes: Option<&str>,
+        closed_at: OffsetDateTime,
+    ) -> Result<bool> {
+        self.with_client(|client| {
+            let mut transaction = client.transaction()?;
+            transaction.execute(
+                "INSERT INTO ScreenshareHistory \
+                (channel, creator, in_question, closer, outcome, notes, created_at, closed_at, assignee) \
+                SELECT id, creator, in_question, $2, $3, $4, created_at, $5, assignee FROM Screenshares \
+                WHERE id = $1",
+                &[
+                    &(id as i64),
+                    &(closer as i64),
+                    &outcome.as_str(),
+                    &notes,
+                    &closed_at.unix_timestamp(),
+                ],
+            )?;
+            let closed = transaction
+                .execute("DELETE FROM Screenshares WHERE id = $1", &[&(id as i64)])?;
+            transaction.commit()?;
+            Ok(closed > 0)
+        })
+    }
+
+    fn fetch_screenshare_history_for(&self, in_question: u64) -> Result<Vec<ClosedScreenshare>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT closer, outcome, notes, closed_at, \
+                assignee FROM ScreenshareHistory WHERE in_question = $1 ORDER BY closed_at DESC",
+                &[&(in_question as i64)],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn fetch_screenshare_history(&self, since: OffsetDateTime) -> Result<Vec<ClosedScreenshare>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT closer, outcome, notes, closed_at, \
+                assignee FROM ScreenshareHistory WHERE closed_at >= $1 ORDER BY closed_at",
+                &[&since.unix_timestamp()],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn add_screenshare_action(
+        &self,
+        staff: u64,
+        user: u64,
+        kind: ScreenshareActionKind,
+        at: OffsetDateTime,
+        response: Option<u64>,
+    ) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "INSERT INTO ScreenshareActions (staff, \"user\", kind, at, response) \
+                values ($1, $2, $3, $4, $5)",
+                &[
+                    &(staff as i64),
+                    &(user as i64),
+                    &kind.as_str(),
+                    &at.unix_timestamp(),
+                    &response.map(|response| response as i64),
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn fetch_screenshare_actions(&self, since: OffsetDateTime) -> Result<Vec<ScreenshareAction>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT staff, kind, response FROM ScreenshareActions \
+                WHERE at >= $1 ORDER BY at, id",
+                &[&since.unix_timestamp()],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn fetch_freezes_for(&self, id: u64) -> Result<Option<Freeze>> {
+        let row = self.with_client(|client| {
+            client.query_opt(
+                "SELECT id, roles, time, admitted_at, text_channel, voice_channel FROM Freezes \
+                WHERE id = $1 LIMIT 1",
+                &[&(id as i64)],
+            )
+        })?;
+        Ok(row.as_ref().and_then(decode))
+    }
+
+    fn add_freeze(&self, id: u64, roles: Ids, time: OffsetDateTime) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "INSERT INTO Freezes (id, roles, time) values ($1, $2, $3)",
+                &[&(id as i64), &roles.to_string(), &time.unix_timestamp()],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn remove_freeze(&self, id: u64) -> Result {
+        self.with_client(|client| {
+            client.execute("DELETE FROM Freezes WHERE id = $1", &[&(id as i64)])
+        })?;
+        Ok(())
+    }
+
+    fn admit_freeze(&self, id: u64, admitted_at: OffsetDateTime) -> Result<bool> {
+        let updated = self.with_client(|client| {
+            client.execute(
+                "UPDATE Freezes SET admitted_at = $1 WHERE id = $2 AND admitted_at IS NULL",
+                &[&admitted_at.unix_timestamp(), &(id as i64)],
+            )
+        })?;
+        Ok(updated > 0)
+    }
+
+    fn set_freeze_channels(&self, id: u64, text: u64, voice: u64) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "UPDATE Freezes SET text_channel = $1, voice_channel = $2 WHERE id = $3",
+                &[&(text as i64), &(voice as i64), &(id as i64)],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn get_screensharers(&self) -> Result<Vec<Screensharer>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT id, freezes FROM ScreensharerStats ORDER BY freezes DESC",
+                &[],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn get_screensharer(&self, user: u64) -> Result<Option<Screensharer>> {
+        let row = self.with_client(|client| {
+            client.query_opt(
+                "SELECT id, freezes FROM ScreensharerStats WHERE id = $1",
+                &[&(user as i64)],
+            )
+        })?;
+        Ok(row.as_ref().and_then(decode))
+    }
+
+    fn set_screensharer(&self, sc: Screensharer) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "INSERT INTO ScreensharerStats (id, freezes) values ($1, $2) \
+                ON CONFLICT (id) DO UPDATE SET freezes = excluded.freezes",
+                &[&(sc.id as i64), &(sc.freezes as i64)],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn record_account(&self, account: &KnownAccount) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "INSERT INTO KnownAccounts (id, name, avatar, invite) values ($1, $2, $3, $4) \
+                ON CONFLICT (id) DO UPDATE SET name = excluded.name, avatar = excluded.avatar, \
+                invite = coalesce(excluded.invite, KnownAccounts.invite)",
+                &[
+                    &(account.id as i64),
+                    &account.name,
+                    &account.avatar,
+                    &account.invite,
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn schedule_job(&self, key: Option<&str>, job: &Job, run_at: OffsetDateTime) -> Result<bool> {
+        let inserted = self.with_client(|client| {
+            client.execute(
+                "INSERT INTO ScheduledJobs (key, kind, payload, run_at) values ($1, $2, $3, $4) \
+                ON CONFLICT (key) DO NOTHING",
+                &[&key, &job.name(), &job.payload(), &run_at.unix_timestamp()],
+            )
+        })?;
+        Ok(inserted > 0)
+    }
+
+    fn fetch_due_jobs(&self, now: OffsetDateTime) -> Result<Vec<ScheduledJob>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT id, payload, run_at, attempts, last_error, locked_until, failed FROM ScheduledJobs \
+                WHERE NOT failed AND run_at <= $1 AND (locked_until IS NULL OR locked_until <= $1) \
+                ORDER BY run_at, id",
+                &[&now.unix_timestamp()],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn claim_job(
+        &self,
+        id: u64,
+        now: OffsetDateTime,
+        locked_until: OffsetDateTime,
+    ) -> Result<bool> {
+        let claimed = self.with_client(|client| {
+            client.execute(
+                "UPDATE ScheduledJobs SET locked_until = $1, attempts = attempts + 1 \
+                WHERE id = $2 AND (locked_until IS NULL OR locked_until <= $3)",
+                &[
+                    &locked_until.unix_timestamp(),
+                    &(id as i64),
+                    &now.unix_timestamp(),
+                ],
+            )
+        })?;
+        Ok(claimed > 0)
+    }
+
+    fn finish_job(&self, id: u64) -> Result {
+        self.with_client(|client| {
+            client.execute("DELETE FROM ScheduledJobs WHERE id = $1", &[&(id as i64)])
+        })?;
+        Ok(())
+    }
+
+    fn reschedule_job(&self, id: u64, run_at: OffsetDateTime, error: Option<&str>) -> Result {
+        self.with_client(|client| match error {
+            Some(error) => client.execute(
+                "UPDATE ScheduledJobs SET run_at = $1, last_error = $2, locked_until = NULL \
+                WHERE id = $3",
+                &[&run_at.unix_timestamp(), &error, &(id as i64)],
+            ),
+            None => client.execute(
+                "UPDATE ScheduledJobs SET run_at = $1, attempts = 0, last_error = NULL, \
+                locked_until = NULL WHERE id = $2",
+                &[&run_at.unix_timestamp(), &(id as i64)],
+            ),
+        })?;
+        Ok(())
+    }
+
+    fn fail_job(&self, id: u64, error: &str) -> Result {
+        self.with_client(|client| {
+            client.execute(
+                "UPDATE ScheduledJobs SET failed = true, last_error = $1, locked_until = NULL \
+                WHERE id = $2",
+                &[&error, &(id as i64)],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn fetch_jobs(&self) -> Result<Vec<ScheduledJob>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT id, payload, run_at, attempts, last_error, locked_until, failed FROM ScheduledJobs ORDER BY run_at, id",
+                &[],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn retry_job(&self, id: u64, now: OffsetDateTime) -> Result<bool> {
+        let updated = self.with_client(|client| {
+            client.execute(
+                "UPDATE ScheduledJobs SET failed = false, attempts = 0, run_at = $1, \
+                locked_until = NULL WHERE id = $2 AND (locked_until IS NULL OR locked_until <= $1)",
+                &[&now.unix_timestamp(), &(id as i64)],
+            )
+        })?;
+        Ok(updated > 0)
+    }
+
+    fn cancel_job(&self, id: u64) -> Result<bool> {
+        let deleted = self.with_client(|client| {
+            client.execute("DELETE FROM ScheduledJobs WHERE id = $1", &[&(id as i64)])
+        })?;
+        Ok(deleted > 0)
+    }
+
+    fn link_minecraft_account(
+        &self,
+        user: u64,
+        ign: &str,
+        uuid: Option<&str>,
+        linked_by: u64,
+        linked_at: OffsetDateTime,
+    ) -> Result<bool> {
+        let inserted = self.with_client(|client| {
+            let mut transaction = client.transaction()?;
+            let inserted = transaction.execute(
+                "INSERT INTO MinecraftAccounts (\"user\", ign, uuid, linked_by, linked_at) \
+                values ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+                &[
+                    &(user as i64),
+                    &ign,
+                    &uuid,
+                    &(linked_by as i64),
+                    &linked_at.unix_timestamp(),
+                ],
+            )?;
+            transaction.execute(
+                "UPDATE MinecraftAccounts SET uuid = $1 \
+                WHERE \"user\" = $2 AND lower(ign) = lower($3) AND uuid IS NULL",
+                &[&uuid, &(user as i64), &ign],
+            )?;
+            transaction.commit()?;
+            Ok(inserted)
+        })?;
+        Ok(inserted > 0)
+    }
+
+    fn unlink_minecraft_account(&self, user: u64, ign: &str) -> Result<bool> {
+        let deleted = self.with_client(|client| {
+            client.execute(
+                "DELETE FROM MinecraftAccounts WHERE \"user\" = $1 AND lower(ign) = lower($2)",
+                &[&(user as i64), &ign],
+            )
+        })?;
+        Ok(deleted > 0)
+    }
+
+    fn fetch_minecraft_accounts(&self, user: u64) -> Result<Vec<MinecraftAccount>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT \"user\", ign, uuid, linked_by, linked_at FROM MinecraftAccounts \
+                WHERE \"user\" = $1 ORDER BY linked_at, lower(ign)",
+                &[&(user as i64)],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn fetch_minecraft_account_users(
+        &self,
+        ign: &str,
+        uuid: Option<&str>,
+    ) -> Result<Vec<MinecraftAccount>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT \"user\", ign, uuid, linked_by, linked_at FROM MinecraftAccounts \
+                WHERE lower(ign) = lower($1) OR uuid = $2 ORDER BY linked_at, \"user\"",
+                &[&ign, &uuid],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+
+    fn fetch_banned_accounts(&self, now: OffsetDateTime) -> Result<Vec<KnownAccount>> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT k.id, k.name, k.avatar, k.invite FROM KnownAccounts k \
+                JOIN ScheduledScrimUnbans s ON s.id = k.id \
+                WHERE NOT s.logged AND (s.time IS NULL OR s.time > $1)",
+                &[&now.unix_timestamp()],
+            )
+        })?;
+        Ok(decode_all(rows))
+    }
+}
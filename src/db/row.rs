@@ -0,0 +1,523 @@
+//! Decoding of query results into the model types, shared by every backend.
+//!
+//! Rows are decoded by column position, so the queries have to select the columns in the order
+//! documented on each [`FromRow`] implementation. A row that can't be decoded is logged and
+//! skipped rather than taking down whatever was reading it.
+
+use std::fmt::Display;
+
+use serenity::model::id::ChannelId;
+use sqlite::Value;
+use time::OffsetDateTime;
+
+use crate::model::*;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The row has fewer columns than the type needs
+    MissingColumn(usize),
+    UnexpectedNull(usize),
+    WrongType {
+        column: usize,
+        expected: &'static str,
+    },
+    /// The column has the right type but its contents don't make sense
+    Invalid {
+        column: usize,
+        reason: String,
+    },
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingColumn(column) => write!(f, "column {} is missing", column),
+            Self::UnexpectedNull(column) => write!(f, "column {} is null", column),
+            Self::WrongType { column, expected } => {
+                write!(f, "column {} is not {}", column, expected)
+            }
+            Self::Invalid { column, reason } => {
+                write!(f, "column {} is invalid: {}", column, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+type DecodeResult<T> = std::result::Result<T, DecodeError>;
+
+/// Column access for a single row of any backend
+pub trait Row {
+    fn integer(&self, column: usize) -> DecodeResult<Option<i64>>;
+    fn text(&self, column: usize) -> DecodeResult<Option<String>>;
+    fn boolean(&self, column: usize) -> DecodeResult<Option<bool>>;
+
+    fn unsigned(&self, column: usize) -> DecodeResult<u64> {
+        required(column, self.optional_unsigned(column)?)
+    }
+
+    fn optional_unsigned(&self, column: usize) -> DecodeResult<Option<u64>> {
+        self.integer(column)?
+            .map(|value| u64::try_from(value).map_err(|err| invalid(column, err)))
+            .transpose()
+    }
+
+    fn string(&self, column: usize) -> DecodeResult<String> {
+        required(column, self.text(column)?)
+    }
+
+    fn timestamp(&self, column: usize) -> DecodeResult<OffsetDateTime> {
+        required(column, self.optional_timestamp(column)?)
+    }
+
+    fn optional_timestamp(&self, column: usize) -> DecodeResult<Option<OffsetDateTime>> {
+        self.integer(column)?
+            .map(|time| {
+                OffsetDateTime::from_unix_timestamp(time).map_err(|err| invalid(column, err))
+            })
+            .transpose()
+    }
+
+    fn flag(&self, column: usize) -> DecodeResult<bool> {
+        required(column, self.boolean(column)?)
+    }
+
+    fn ids(&self, column: usize) -> DecodeResult<Ids> {
+        Ids::try_from(self.string(column)?).map_err(|err| invalid(column, err))
+    }
+}
+
+fn required<T>(column: usize, value: Option<T>) -> DecodeResult<T> {
+    value.ok_or(DecodeError::UnexpectedNull(column))
+}
+
+fn invalid(column: usize, reason: impl Display) -> DecodeError {
+    DecodeError::Invalid {
+        column,
+        reason: reason.to_string(),
+    }
+}
+
+impl Row for [Value] {
+    fn integer(&self, column: usize) -> DecodeResult<Option<i64>> {
+        match self.get(column) {
+            None => Err(DecodeError::MissingColumn(column)),
+            Some(Value::Null) => Ok(None),
+            Some(Value::Integer(value)) => Ok(Some(*value)),
+            Some(_) => Err(DecodeError::WrongType {
+                column,
+                expected: "an integer",
+            }),
+        }
+    }
+
+    fn text(&self, column: usize) -> DecodeResult<Option<String>> {
+        match self.get(column) {
+            None => Err(DecodeError::MissingColumn(column)),
+            Some(Value::Null) => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(DecodeError::WrongType {
+                column,
+                expected: "text",
+            }),
+        }
+    }
+
+    /// Sqlite has no booleans, they are stored as 0 or 1
+    fn boolean(&self, column: usize) -> DecodeResult<Option<bool>> {
+        match self.integer(column)? {
+            None => Ok(None),
+            Some(0) => Ok(Some(false)),
+            Some(1) => Ok(Some(true)),
+            Some(value) => Err(invalid(column, format!("{} is not a boolean", value))),
+        }
+    }
+}
+
+impl Row for postgres::Row {
+    fn integer(&self, column: usize) -> DecodeResult<Option<i64>> {
+        if column >= self.len() {
+            return Err(DecodeError::MissingColumn(column));
+        }
+        self.try_get(column).map_err(|_| DecodeError::WrongType {
+            column,
+            expected: "a bigint",
+        })
+    }
+
+    fn text(&self, column: usize) -> DecodeResult<Option<String>> {
+        if column >= self.len() {
+            return Err(DecodeError::MissingColumn(column));
+        }
+        self.try_get(column).map_err(|_| DecodeError::WrongType {
+            column,
+            expected: "text",
+        })
+    }
+
+    fn boolean(&self, column: usize) -> DecodeResult<Option<bool>> {
+        if column >= self.len() {
+            return Err(DecodeError::MissingColumn(column));
+        }
+        self.try_get(column).map_err(|_| DecodeError::WrongType {
+            column,
+            expected: "a boolean",
+        })
+    }
+}
+
+pub trait FromRow: Sized {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self>;
+}
+
+/// Decodes a row, logging it and returning `None` if it is malformed
+pub fn decode<T: FromRow, R: Row + ?Sized>(row: &R) -> Option<T> {
+    T::from_row(row)
+        .map_err(|err| {
+            tracing::error!(
+                "Skipping malformed {} row: {}",
+                std::any::type_name::<T>(),
+                err
+            )
+        })
+        .ok()
+}
+
+/// `id, time, roles, logged`
+impl FromRow for ScrimUnban {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            id: row.unsigned(0)?,
+            date: row.optional_timestamp(1)?,
+            roles: row.ids(2)?,
+            logged: row.flag(3)?,
+        })
+    }
+}
+
+/// `id, user, staff, reason, issued_at, expires_at, revoked_by, revoke_reason, revoked_at`
+impl FromRow for ScrimBan {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            id: row.unsigned(0)?,
+            user: row.unsigned(1)?,
+            staff: row.optional_unsigned(2)?,
+            reason: row.string(3)?,
+            issued_at: row.optional_timestamp(4)?,
+            expires_at: row.optional_timestamp(5)?,
+            revoked_by: row.optional_unsigned(6)?,
+            revoke_reason: row.text(7)?,
+            revoked_at: row.optional_timestamp(8)?,
+        })
+    }
+}
+
+/// `staff, edited_at, old_expires_at, new_expires_at, old_reason, new_reason`
+impl FromRow for ScrimBanEdit {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            staff: row.unsigned(0)?,
+            edited_at: row.timestamp(1)?,
+            old_expires_at: row.optional_timestamp(2)?,
+            new_expires_at: row.optional_timestamp(3)?,
+            old_reason: row.string(4)?,
+            new_reason: row.string(5)?,
+        })
+    }
+}
+
+/// `id, ban, user, appeal, created_at, status, reviewed_by, reviewed_at`
+impl FromRow for Appeal {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            id: row.unsigned(0)?,
+            ban: row.unsigned(1)?,
+            user: row.unsigned(2)?,
+            appeal: row.string(3)?,
+            created_at: row.timestamp(4)?,
+            status: row.string(5)?.parse().map_err(|err| invalid(5, err))?,
+            reviewed_by: row.optional_unsigned(6)?,
+            reviewed_at: row.optional_timestamp(7)?,
+        })
+    }
+}
+
+/// `userid, id, created_at, note, creator`
+impl FromRow for Note {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            userid: row.unsigned(0)?,
+            id: row.unsigned(1)?,
+            created_at: row.timestamp(2)?,
+            note: row.string(3)?,
+            creator: row.unsigned(4)?,
+        })
+    }
+}
+
+/// `id, roles, time, admitted_at, text_channel, voice_channel`
+impl FromRow for Freeze {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            id: row.unsigned(0)?,
+            roles: row.ids(1)?.into(),
+            time: row.timestamp(2)?,
+            admitted_at: row.optional_timestamp(3)?,
+            text_channel: row.optional_unsigned(4)?.map(ChannelId),
+            voice_channel: row.optional_unsigned(5)?.map(ChannelId),
+        })
+    }
+}
+
+/// `id, creator, in_question, created_at, assignee`
+impl FromRow for Screenshare {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            id: row.unsigned(0)?,
+            creator: row.unsigned(1)?,
+            in_question: row.unsigned(2)?,
+            created_at: row.timestamp(3)?,
+            assignee: row.optional_unsigned(4)?,
+        })
+    }
+}
+
+/// `user, emoji, trigger`
+impl FromRow for CustomReaction {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            user: row.unsigned(0)?,
+            emoji: row.string(1)?,
+            trigger: row.string(2)?,
+        })
+    }
+}
+
+/// `id, name, avatar, invite`
+impl FromRow for KnownAccount {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            id: row.unsigned(0)?,
+            name: row.string(1)?,
+            avatar: row.text(2)?,
+            invite: row.text(3)?,
+        })
+    }
+}
+
+/// `user, ign, uuid, linked_by, linked_at`
+impl FromRow for MinecraftAccount {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            user: row.unsigned(0)?,
+            ign: row.string(1)?,
+            uuid: row.text(2)?,
+            linked_by: row.unsigned(3)?,
+            linked_at: row.timestamp(4)?,
+        })
+    }
+}
+
+/// `closer, outcome, notes, closed_at, assignee`
+impl FromRow for ClosedScreenshare {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            closer: row.unsigned(0)?,
+            outcome: row.string(1)?.parse().map_err(|err| invalid(1, err))?,
+            notes: row.text(2)?,
+            closed_at: row.timestamp(3)?,
+            assignee: row.optional_unsigned(4)?,
+        })
+    }
+}
+
+/// `staff, kind, response`
+impl FromRow for ScreenshareAction {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            staff: row.unsigned(0)?,
+            kind: row.string(1)?.parse().map_err(|err| invalid(1, err))?,
+            response: row.optional_unsigned(2)?,
+        })
+    }
+}
+
+/// `user, added_at`
+impl FromRow for ScreenshareReporter {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            user: row.unsigned(0)?,
+            added_at: row.timestamp(1)?,
+        })
+    }
+}
+
+/// `id, payload, run_at, attempts, last_error, locked_until, failed`
+impl FromRow for ScheduledJob {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            id: row.unsigned(0)?,
+            job: row.string(1)?.parse().map_err(|err| invalid(1, err))?,
+            run_at: row.timestamp(2)?,
+            attempts: row.unsigned(3)?,
+            last_error: row.text(4)?,
+            locked_until: row.optional_timestamp(5)?,
+            failed: row.flag(6)?,
+        })
+    }
+}
+
+/// `id, freezes`
+impl FromRow for Screensharer {
+    fn from_row<R: Row + ?Sized>(row: &R) -> DecodeResult<Self> {
+        Ok(Self {
+            id: row.unsigned(0)?,
+            freezes: row.unsigned(1)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(value: i64) -> Value {
+        Value::Integer(value)
+    }
+
+    fn text(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    #[test]
+    fn decode_valid_rows() {
+        let unban =
+            ScrimUnban::from_row(&[int(1), int(1_700_000_000), text("2,3"), int(0)][..]).unwrap();
+        assert!(unban.date.is_some() && !unban.was_logged());
+        assert_eq!((unban.id, unban.roles.0), (1, vec![2, 3]));
+
+        let permanent = ScrimUnban::from_row(&[int(1), Value::Null, text(""), int(0)][..]).unwrap();
+        assert!(permanent.is_permanent() && !permanent.is_expired());
+        assert!(permanent.roles.0.is_empty());
+
+        let freeze =
+            Freeze::from_row(&[int(4), text("5"), int(0), Value::Null, int(6), Value::Null][..])
+                .unwrap();
+        assert_eq!(freeze.roles.len(), 1);
+        assert_eq!(freeze.text_channel, Some(ChannelId(6)));
+        assert!(freeze.voice_channel.is_none());
+    }
+
+    #[test]
+    fn bad_ids() {
+        assert!(matches!(
+            ScrimUnban::from_row(&[int(1), Value::Null, text("2,abc"), int(0)][..]),
+            Err(DecodeError::Invalid { column: 2, .. })
+        ));
+        assert!(matches!(
+            ScrimUnban::from_row(&[int(1), Value::Null, text(""), int(2)][..]),
+            Err(DecodeError::Invalid { column: 3, .. })
+        ));
+        assert!(matches!(
+            Screensharer::from_row(&[int(-1), int(0)][..]),
+            Err(DecodeError::Invalid { column: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn unknown_appeal_status() {
+        let appeal = |status| {
+            Appeal::from_row(
+                &[
+                    int(1),
+                    int(2),
+                    int(3),
+                    text("please"),
+                    int(0),
+                    text(status),
+                    Value::Null,
+                    Value::Null,
+                ][..],
+            )
+        };
+        assert_eq!(appeal("reduced").unwrap().status, AppealStatus::Reduced);
+        assert!(matches!(
+            appeal("maybe"),
+            Err(DecodeError::Invalid { column: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn unexpected_null() {
+        assert!(matches!(
+            Note::from_row(&[int(1), int(1), int(0), Value::Null, int(2)][..]),
+            Err(DecodeError::UnexpectedNull(3))
+        ));
+        assert!(matches!(
+            Freeze::from_row(
+                &[
+                    int(1),
+                    text(""),
+                    Value::Null,
+                    Value::Null,
+                    Value::Null,
+                    Value::Null
+                ][..]
+            ),
+            Err(DecodeError::UnexpectedNull(2))
+        ));
+    }
+
+    #[test]
+    fn wrong_type() {
+        assert!(matches!(
+            Screenshare::from_row(&[int(1), text("2"), int(3)][..]),
+            Err(DecodeError::WrongType { column: 1, .. })
+        ));
+        assert!(matches!(
+            CustomReaction::from_row(&[int(1), Value::Float(1.0), text("hi")][..]),
+            Err(DecodeError::WrongType { column: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn missing_column() {
+        assert!(matches!(
+            CustomReaction::from_row(&[int(1), text("👍")][..]),
+            Err(DecodeError::MissingColumn(2))
+        ));
+    }
+
+    #[test]
+    fn timestamp_out_of_range() {
+        assert!(matches!(
+            Freeze::from_row(
+                &[
+                    int(1),
+                    text(""),
+                    int(i64::MAX),
+                    Value::Null,
+                    Value::Null,
+                    Value::Null
+                ][..]
+            ),
+            Err(DecodeError::Invalid { column: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn decode_skips_malformed() {
+        let rows = [
+            vec![int(1), int(2)],
+            vec![int(3), text("lots")],
+            vec![int(4), int(5)],
+        ];
+        let screensharers = rows
+            .iter()
+            .filter_map(|row| decode::<Screensharer, _>(&row[..]))
+            .map(|sc| sc.id)
+            .collect::<Vec<_>>();
+        assert_eq!(screensharers, vec![1, 4]);
+    }
+}
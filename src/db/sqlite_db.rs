@@ -0,0 +1,914 @@
+use std::{
+    cmp::Reverse,
+    path::Path,
+    sync::{Mutex, MutexGuard},
+};
+
+use sqlite::{Connection, Value};
+use time::OffsetDateTime;
+
+use super::{
+    migrations,
+    row::{decode, FromRow},
+    Database, Result,
+};
+use crate::model::*;
+
+type SqliteResult<T = ()> = std::result::Result<T, sqlite::Error>;
+
+pub struct SqliteDatabase {
+    sqlite: Mutex<Connection>,
+}
+
+/// Runs a single statement with the given parameters bound in order
+fn execute(db: &Connection, query: &str, params: &[Value]) -> SqliteResult {
+    let mut cursor = db.prepare(query)?.into_cursor();
+    cursor.bind(params)?;
+    while cursor.next()?.is_some() {}
+    Ok(())
+}
+
+fn count(db: &Connection, query: &str, params: &[Value]) -> SqliteResult<i64> {
+    let mut cursor = db.prepare(query)?.into_cursor();
+    cursor.bind(params)?;
+    let count = cursor.next()?.and_then(|row| row[0].as_integer());
+    Ok(count.unwrap_or_default())
+}
+
+fn integer(value: u64) -> Value {
+    Value::Integer(value as i64)
+}
+
+fn optional_integer(value: Option<u64>) -> Value {
+    value.map_or(Value::Null, integer)
+}
+
+fn timestamp(date: Option<OffsetDateTime>) -> Value {
+    date.map_or(Value::Null, |date| Value::Integer(date.unix_timestamp()))
+}
+
+impl SqliteDatabase {
+    pub fn init() -> Result<Self> {
+        // Create sqlite file
+        let mut path = crate::consts::DATABASE_PATH.clone();
+        let _ = std::fs::create_dir_all(&path);
+        path.push("bridge-scrims.sqlite");
+        if !path.as_path().exists() {
+            std::fs::File::create(&path).expect("Cannot create database file");
+        }
+
+        Self::open(path)
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        migrations::migrate_sqlite(&conn)?;
+
+        Ok(Self {
+            sqlite: Mutex::new(conn),
+        })
+    }
+
+    fn get_lock<T, F>(&self, predicate: F) -> SqliteResult<T>
+    where
+        F: FnOnce(MutexGuard<Connection>) -> SqliteResult<T>,
+    {
+        self.sqlite.lock().map_or_else(
+            |_| {
+                Err(sqlite::Error {
+                    code: Some(6),
+                    message: Some("Could not lock database".to_string()),
+                })
+            },
+            predicate,
+        )
+    }
+
+    fn execute(&self, query: &str, params: &[Value]) -> SqliteResult {
+        self.get_lock(|db| execute(&db, query, params))
+    }
+
+    /// Fetches every row that decodes, malformed rows are logged and skipped
+    fn fetch<T: FromRow>(&self, query: &str, params: &[Value]) -> SqliteResult<Vec<T>> {
+        self.get_lock(|db| {
+            let mut rows = Vec::new();
+            let mut cursor = db.prepare(query)?.into_cursor();
+            cursor.bind(params)?;
+            while let Some(row) = cursor.next()? {
+                rows.extend(decode(row));
+            }
+            Ok(rows)
+        })
+    }
+
+    fn fetch_one<T: FromRow>(&self, query: &str, params: &[Value]) -> SqliteResult<Option<T>> {
+        Ok(self.fetch(query, params)?.into_iter().next())
+    }
+
+    fn count(&self, query: &str, params: &[Value]) -> SqliteResult<i64> {
+        self.get_lock(|db| count(&db, query, params))
+    }
+}
+
+impl Database for SqliteDatabase {
+    fn fetch_scrim_unbans(&self) -> Result<Vec<ScrimUnban>> {
+        Ok(self.fetch(
+            "SELECT id, time, roles, logged FROM ScheduledScrimUnbans",
+            &[],
+        )?)
+    }
+
+    fn add_scrim_unban(&self, id: u64, unban_date: Option<OffsetDateTime>, roles: &Ids) -> Result {
+        Ok(self.execute(
+            "INSERT INTO ScheduledScrimUnbans (id, time, roles) values (?, ?, ?)",
+            &[
+                integer(id),
+                timestamp(unban_date),
+                Value::String(roles.to_string()),
+            ],
+        )?)
+    }
+
+    fn modify_scrim_unban(
+        &self,
+        id: u64,
+        unban_date: Option<OffsetDateTime>,
+        roles: &Ids,
+    ) -> Result {
+        Ok(self.execute(
+            "UPDATE ScheduledScrimUnbans SET time = ?, roles = ?, logged = 0 WHERE id = ?",
+            &[
+                timestamp(unban_date),
+                Value::String(roles.to_string()),
+                integer(id),
+            ],
+        )?)
+    }
+
+    fn mark_scrim_unban_logged(&self, id: u64) -> Result {
+        Ok(self.execute(
+            "UPDATE ScheduledScrimUnbans SET logged = 1 WHERE id = ?",
+            &[integer(id)],
+        )?)
+    }
+
+    fn remove_scrim_unban(&self, id: u64) -> Result {
+        Ok(self.execute(
+            "DELETE FROM ScheduledScrimUnbans WHERE id = ?",
+            &[integer(id)],
+        )?)
+    }
+
+    fn add_scrim_ban(
+        &self,
+        user: u64,
+        staff: u64,
+        reason: &str,
+        issued_at: OffsetDateTime,
+        expires_at: Option<OffsetDateTime>,
+    ) -> Result<i64> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "INSERT INTO ScrimBans (user, staff, reason, issued_at, expires_at) values (?, ?, ?, ?, ?)",
+                &[
+                    integer(user),
+                    integer(staff),
+                    Value::String(reason.to_string()),
+                    timestamp(Some(issued_at)),
+                    timestamp(expires_at),
+                ],
+            )?;
+            let mut stmt = db.prepare("SELECT last_insert_rowid()")?;
+            stmt.next()?;
+            stmt.read::<i64>(0)
+        })?)
+    }
+
+    fn revoke_scrim_bans(
+        &self,
+        user: u64,
+        revoked_by: Option<u64>,
+        reason: &str,
+        revoked_at: OffsetDateTime,
+    ) -> Result {
+        Ok(self.execute(
+            "UPDATE ScrimBans SET revoked_by = ?, revoke_reason = ?, revoked_at = ? \
+            WHERE user = ? AND revoked_at IS NULL",
+            &[
+                optional_integer(revoked_by),
+                Value::String(reason.to_string()),
+                timestamp(Some(revoked_at)),
+                integer(user),
+            ],
+        )?)
+    }
+
+    fn fetch_scrim_ban_history(&self, user: u64) -> Result<Vec<ScrimBan>> {
+        Ok(self.fetch(
+            "SELECT id, user, staff, reason, issued_at, expires_at, revoked_by, revoke_reason, revoked_at \
+            FROM ScrimBans WHERE user = ? ORDER BY id DESC",
+            &[integer(user)],
+        )?)
+    }
+
+    fn fetch_scrim_ban(&self, id: u64) -> Result<Option<ScrimBan>> {
+        Ok(self.fetch_one(
+            "SELECT id, user, staff, reason, issued_at, expires_at, revoked_by, revoke_reason, revoked_at \
+            FROM ScrimBans WHERE id = ?",
+            &[integer(id)],
+        )?)
+    }
+
+    fn edit_scrim_ban(
+        &self,
+        ban: u64,
+        staff: u64,
+        edited_at: OffsetDateTime,
+        expires_at: Option<OffsetDateTime>,
+        reason: &str,
+    ) -> Result {
+        Ok(self.get_lock(|db| {
+            execute(&db, "BEGIN", &[])?;
+            let res = execute(
+                &db,
+                "INSERT INTO ScrimBanEdits \
+                (ban, staff, edited_at, old_expires_at, new_expires_at, old_reason, new_reason) \
+                SELECT id, ?, ?, expires_at, ?, reason, ? FROM ScrimBans WHERE id = ?",
+                &[
+                    integer(staff),
+                    timestamp(Some(edited_at)),
+                    timestamp(expires_at),
+                    Value::String(reason.to_string()),
+                    integer(ban),
+                ],
+            )
+            .and_then(|_| {
+                execute(
+                    &db,
+                    "UPDATE ScrimBans SET expires_at = ?, reason = ? WHERE id = ?",
+                    &[
+                        timestamp(expires_at),
+                        Value::String(reason.to_string()),
+                        integer(ban),
+                    ],
+                )
+            });
+            match res {
+                Ok(_) => execute(&db, "COMMIT", &[]),
+                Err(err) => {
+                    let _ = execute(&db, "ROLLBACK", &[]);
+                    Err(err)
+                }
+            }
+        })?)
+    }
+
+    fn fetch_scrim_ban_edits(&self, ban: u64) -> Result<Vec<ScrimBanEdit>> {
+        Ok(self.fetch(
+            "SELECT staff, edited_at, old_expires_at, new_expires_at, old_reason, new_reason \
+            FROM ScrimBanEdits WHERE ban = ? ORDER BY id",
+            &[integer(ban)],
+        )?)
+    }
+
+    fn add_appeal(
+        &self,
+        ban: u64,
+        user: u64,
+        appeal: &str,
+        created_at: OffsetDateTime,
+    ) -> Result<i64> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "INSERT INTO Appeals (ban, user, appeal, created_at) values (?, ?, ?, ?)",
+                &[
+                    integer(ban),
+                    integer(user),
+                    Value::String(appeal.to_string()),
+                    timestamp(Some(created_at)),
+                ],
+            )?;
+            let mut stmt = db.prepare("SELECT last_insert_rowid()")?;
+            stmt.next()?;
+            stmt.read::<i64>(0)
+        })?)
+    }
+
+    fn fetch_appeal(&self, id: u64) -> Result<Option<Appeal>> {
+        Ok(self.fetch_one(
+            "SELECT id, ban, user, appeal, created_at, status, reviewed_by, reviewed_at \
+            FROM Appeals WHERE id = ?",
+            &[integer(id)],
+        )?)
+    }
+
+    fn fetch_appeal_for_ban(&self, ban: u64) -> Result<Option<Appeal>> {
+        Ok(self.fetch_one(
+            "SELECT id, ban, user, appeal, created_at, status, reviewed_by, reviewed_at \
+            FROM Appeals WHERE ban = ?",
+            &[integer(ban)],
+        )?)
+    }
+
+    fn fetch_appeals_for_user(&self, user: u64) -> Result<Vec<Appeal>> {
+        Ok(self.fetch(
+            "SELECT id, ban, user, appeal, created_at, status, reviewed_by, reviewed_at \
+            FROM Appeals WHERE user = ?",
+            &[integer(user)],
+        )?)
+    }
+
+    fn decide_appeal(
+        &self,
+        id: u64,
+        status: AppealStatus,
+        reviewed_by: u64,
+        reviewed_at: OffsetDateTime,
+    ) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "UPDATE Appeals SET status = ?, reviewed_by = ?, reviewed_at = ? \
+                WHERE id = ? AND status = 'pending'",
+                &[
+                    Value::String(status.to_string()),
+                    integer(reviewed_by),
+                    timestamp(Some(reviewed_at)),
+                    integer(id),
+                ],
+            )?;
+            Ok(db.change_count() > 0)
+        })?)
+    }
+
+    fn reopen_appeal(&self, id: u64) -> Result {
+        Ok(self.execute(
+            "UPDATE Appeals SET status = 'pending', reviewed_by = NULL, reviewed_at = NULL \
+            WHERE id = ?",
+            &[integer(id)],
+        )?)
+    }
+
+    fn fetch_custom_reactions(&self) -> Result<Vec<CustomReaction>> {
+        Ok(self.fetch("SELECT user, emoji, trigger FROM Reaction", &[])?)
+    }
+
+    fn fetch_custom_reactions_for(&self, user: u64) -> Result<Vec<CustomReaction>> {
+        Ok(self.fetch(
+            "SELECT user, emoji, trigger FROM Reaction WHERE user = ?",
+            &[integer(user)],
+        )?)
+    }
+
+    fn fetch_custom_reactions_with_trigger(&self, trigger: &str) -> Result<Vec<CustomReaction>> {
+        Ok(self.fetch(
+            "SELECT user, emoji, trigger FROM Reaction WHERE trigger = ?",
+            &[Value::String(trigger.to_string())],
+        )?)
+    }
+
+    fn add_custom_reaction(&self, user: u64, emoji: &str, trigger: &str) -> Result {
+        Ok(self.execute(
+            "INSERT INTO Reaction (user, emoji, trigger) values (?, ?, ?)",
+            &[
+                integer(user),
+                Value::String(emoji.to_string()),
+                Value::String(trigger.to_string()),
+            ],
+        )?)
+    }
+
+    fn remove_custom_reaction(&self, user: u64) -> Result {
+        Ok(self.execute("DELETE FROM Reaction WHERE user = ?", &[integer(user)])?)
+    }
+
+    fn fetch_notes_for(&self, userid: u64) -> Result<Vec<Note>> {
+        Ok(self.fetch(
+            "SELECT userid, id, created_at, note, creator FROM Notes WHERE userid = ? ORDER BY id",
+            &[integer(userid)],
+        )?)
+    }
+
+    fn add_note(
+        &self,
+        userid: u64,
+        created_at: OffsetDateTime,
+        note: &str,
+        creator: u64,
+    ) -> Result<i64> {
+        Ok(self.get_lock(|db| {
+            execute(&db, "BEGIN", &[])?;
+            let res = count(
+                &db,
+                "SELECT count(*) FROM Notes WHERE userid = ?",
+                &[integer(userid)],
+            )
+            .and_then(|count| {
+                execute(
+                    &db,
+                    "INSERT INTO Notes (userid, id, created_at, note, creator) values (?, ?, ?, ?, ?)",
+                    &[
+                        integer(userid),
+                        Value::Integer(count + 1),
+                        Value::Integer(created_at.unix_timestamp()),
+                        Value::String(note.to_string()),
+                        integer(creator),
+                    ],
+                )?;
+                Ok(count + 1)
+            });
+            match res {
+                Ok(id) => execute(&db, "COMMIT", &[]).map(|_| id),
+                Err(err) => {
+                    let _ = execute(&db, "ROLLBACK", &[]);
+                    Err(err)
+                }
+            }
+        })?)
+    }
+
+    fn remove_note(&self, userid: u64, id: u64) -> Result {
+        Ok(self.get_lock(|db| {
+            let params = [integer(userid), integer(id)];
+            execute(&db, "BEGIN", &[])?;
+            let res = execute(
+                &db,
+                "DELETE FROM Notes WHERE userid = ? AND id = ?",
+                &params,
+            )
+            .and_then(|_| {
+                execute(
+                    &db,
+                    "UPDATE Notes SET id = id - 1 WHERE userid = ? AND id >= ?",
+                    &params,
+                )
+            });
+            match res {
+                Ok(_) => execute(&db, "COMMIT", &[]),
+                Err(err) => {
+                    let _ = execute(&db, "ROLLBACK", &[]);
+                    Err(err)
+                }
+            }
+        })?)
+    }
+
+    fn fetch_screenshares_for(&self, id: u64) -> Result<Option<Screenshare>> {
+        Ok(self.fetch_one(
+            "SELECT id, creator, in_question, created_at, assignee FROM Screenshares \
+            WHERE id = ?",
+            &[integer(id)],
+        )?)
+    }
+
+    fn count_screenshares(&self) -> Result<i64> {
+        Ok(self.count("SELECT count(*) FROM Screenshares", &[])?)
+    }
+
+    fn fetch_screenshares(&self) -> Result<Vec<Screenshare>> {
+        Ok(self.fetch(
+            "SELECT id, creator, in_question, created_at, assignee FROM Screenshares",
+            &[],
+        )?)
+    }
+
+    fn add_screenshare(
+        &self,
+        id: u64,
+        creator: u64,
+        in_question: u64,
+        created_at: OffsetDateTime,
+    ) -> Result {
+        Ok(self.execute(
+            "INSERT INTO Screenshares (id, creator, in_question, created_at) values (?, ?, ?, ?)",
+            &[
+                integer(id),
+                integer(creator),
+                integer(in_question),
+                timestamp(Some(created_at)),
+            ],
+        )?)
+    }
+
+    fn remove_screenshare(&self, id: u64) -> Result {
+        Ok(self.execute("DELETE FROM Screenshares WHERE id = ?", &[integer(id)])?)
+    }
+
+    fn claim_screenshare(&self, id: u64, assignee: u64) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "UPDATE Screenshares SET assignee = ? WHERE id = ? AND assignee IS NULL",
+                &[integer(assignee), integer(id)],
+            )?;
+            Ok(db.change_count() > 0)
+        })?)
+    }
+
+    fn add_screenshare_reporter(
+        &self,
+        channel: u64,
+        user: u64,
+        added_at: OffsetDateTime,
+    ) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "INSERT OR IGNORE INTO ScreenshareReporters (channel, user, added_at) \
+                values (?, ?, ?)",
+                &[integer(channel), integer(user), timestamp(Some(added_at))],
+            )?;
+            Ok(db.change_count() > 0)
+        })?)
+    }
+
+    fn fetch_screenshare_reporters(&self, channel: u64) -> Result<Vec<ScreenshareReporter>> {
+        Ok(self.fetch(
+            "SELECT user, added_at FROM ScreenshareReporters WHERE channel = ? \
+            ORDER BY added_at, user",
+            &[integer(channel)],
+        )?)
+    }
+
+    fn assign_screenshare(&self, id: u64, assignee: Option<u64>) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "UPDATE Screenshares SET assignee = ? WHERE id = ?",
+                &[optional_integer(assignee), integer(id)],
+            )?;
+            Ok(db.change_count() > 0)
+        })?)
+    }
+
+    fn close_screenshare(
+        &self,
+        id: u64,
+        closer: u64,
+        outcome: ScreenshareOutcome,
+        notes: Option<&str>,
+        closed_at: OffsetDateTime,
+    ) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(&db, "BEGIN", &[])?;
+            let res = execute(
+                &db,
+                "INSERT INTO ScreenshareHistory \
+                (channel, creator, in_question, closer, outcome, notes, created_at, closed_at, assignee) \
+                SELECT id, creator, in_question, ?, ?, ?, created_at, ?, assignee FROM Screenshares \
+                WHERE id = ?",
+                &[
+                    integer(closer),
+                    Value::String(outcome.to_string()),
+                    notes.map_or(Value::Null, |notes| Value::String(notes.to_string())),
+                    timestamp(Some(closed_at)),
+                    integer(id),
+                ],
+            )
+            .and_then(|_| execute(&db, "DELETE FROM Screenshares WHERE id = ?", &[integer(id)]));
+            match res {
+                Ok(_) => {
+                    let closed = db.change_count() > 0;
+                    execute(&db, "COMMIT", &[])?;
+                    Ok(closed)
+                }
+                Err(err) => {
+                    let _ = execute(&db, "ROLLBACK", &[]);
+                    Err(err)
+                }
+            }
+        })?)
+    }
+
+    fn fetch_screenshare_history_for(&self, in_question: u64) -> Result<Vec<ClosedScreenshare>> {
+        Ok(self.fetch(
+            "SELECT closer, outcome, notes, closed_at, \
+            assignee FROM ScreenshareHistory WHERE in_question = ? ORDER BY closed_at DESC",
+            &[integer(in_question)],
+        )?)
+    }
+
+    fn fetch_screenshare_history(&self, since: OffsetDateTime) -> Result<Vec<ClosedScreenshare>> {
+        Ok(self.fetch(
+            "SELECT closer, outcome, notes, closed_at, \
+            assignee FROM ScreenshareHistory WHERE closed_at >= ? ORDER BY closed_at",
+            &[timestamp(Some(since))],
+        )?)
+    }
+
+    fn add_screenshare_action(
+        &self,
+        staff: u64,
+        user: u64,
+        kind: ScreenshareActionKind,
+        at: OffsetDateTime,
+        response: Option<u64>,
+    ) -> Result {
+        Ok(self.execute(
+            "INSERT INTO ScreenshareActions (staff, user, kind, at, response) values (?, ?, ?, ?, ?)",
+            &[
+                integer(staff),
+                integer(user),
+                Value::String(kind.to_string()),
+                timestamp(Some(at)),
+                optional_integer(response),
+            ],
+        )?)
+    }
+
+    fn fetch_screenshare_actions(&self, since: OffsetDateTime) -> Result<Vec<ScreenshareAction>> {
+        Ok(self.fetch(
+            "SELECT staff, kind, response FROM ScreenshareActions \
+            WHERE at >= ? ORDER BY at, id",
+            &[timestamp(Some(since))],
+        )?)
+    }
+
+    fn fetch_freezes_for(&self, id: u64) -> Result<Option<Freeze>> {
+        Ok(self.fetch_one(
+            "SELECT id, roles, time, admitted_at, text_channel, voice_channel FROM Freezes \
+            WHERE id = ?",
+            &[integer(id)],
+        )?)
+    }
+
+    fn add_freeze(&self, id: u64, roles: Ids, time: OffsetDateTime) -> Result {
+        Ok(self.execute(
+            "INSERT INTO Freezes (id, roles, time) values (?, ?, ?)",
+            &[
+                integer(id),
+                Value::String(roles.to_string()),
+                Value::Integer(time.unix_timestamp()),
+            ],
+        )?)
+    }
+
+    fn remove_freeze(&self, id: u64) -> Result {
+        Ok(self.execute("DELETE FROM Freezes WHERE id = ?", &[integer(id)])?)
+    }
+
+    fn admit_freeze(&self, id: u64, admitted_at: OffsetDateTime) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "UPDATE Freezes SET admitted_at = ? WHERE id = ? AND admitted_at IS NULL",
+                &[timestamp(Some(admitted_at)), integer(id)],
+            )?;
+            Ok(db.change_count() > 0)
+        })?)
+    }
+
+    fn set_freeze_channels(&self, id: u64, text: u64, voice: u64) -> Result {
+        Ok(self.execute(
+            "UPDATE Freezes SET text_channel = ?, voice_channel = ? WHERE id = ?",
+            &[integer(text), integer(voice), integer(id)],
+        )?)
+    }
+
+    fn get_screensharers(&self) -> Result<Vec<Screensharer>> {
+        let mut result: Vec<Screensharer> =
+            self.fetch("SELECT id, freezes FROM ScreensharerStats", &[])?;
+        result.sort_unstable_by_key(|x| Reverse(x.freezes));
+        Ok(result)
+    }
+
+    fn get_screensharer(&self, user: u64) -> Result<Option<Screensharer>> {
+        Ok(self.fetch_one(
+            "SELECT id, freezes FROM ScreensharerStats WHERE id = ?",
+            &[integer(user)],
+        )?)
+    }
+
+    fn set_screensharer(&self, sc: Screensharer) -> Result {
+        Ok(self.execute(
+            "INSERT INTO ScreensharerStats (id, freezes) VALUES (?, ?) \
+            ON CONFLICT (id) DO UPDATE SET freezes = excluded.freezes",
+            &[integer(sc.id), integer(sc.freezes)],
+        )?)
+    }
+
+    fn record_account(&self, account: &KnownAccount) -> Result {
+        let text = |value: &Option<String>| value.clone().map_or(Value::Null, Value::String);
+        Ok(self.execute(
+            "INSERT INTO KnownAccounts (id, name, avatar, invite) VALUES (?, ?, ?, ?) \
+            ON CONFLICT (id) DO UPDATE SET name = excluded.name, avatar = excluded.avatar, \
+            invite = coalesce(excluded.invite, KnownAccounts.invite)",
+            &[
+                integer(account.id),
+                Value::String(account.name.clone()),
+                text(&account.avatar),
+                text(&account.invite),
+            ],
+        )?)
+    }
+
+    fn schedule_job(&self, key: Option<&str>, job: &Job, run_at: OffsetDateTime) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "INSERT INTO ScheduledJobs (key, kind, payload, run_at) values (?, ?, ?, ?) \
+                ON CONFLICT (key) DO NOTHING",
+                &[
+                    key.map_or(Value::Null, |key| Value::String(key.to_string())),
+                    Value::String(job.name().to_string()),
+                    Value::String(job.payload()),
+                    timestamp(Some(run_at)),
+                ],
+            )?;
+            Ok(db.change_count() > 0)
+        })?)
+    }
+
+    fn fetch_due_jobs(&self, now: OffsetDateTime) -> Result<Vec<ScheduledJob>> {
+        Ok(self.fetch(
+            "SELECT id, payload, run_at, attempts, last_error, locked_until, failed FROM ScheduledJobs \
+            WHERE failed = 0 AND run_at <= ?1 AND (locked_until IS NULL OR locked_until <= ?1) \
+            ORDER BY run_at, id",
+            &[timestamp(Some(now))],
+        )?)
+    }
+
+    fn claim_job(
+        &self,
+        id: u64,
+        now: OffsetDateTime,
+        locked_until: OffsetDateTime,
+    ) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "UPDATE ScheduledJobs SET locked_until = ?, attempts = attempts + 1 \
+                WHERE id = ? AND (locked_until IS NULL OR locked_until <= ?)",
+                &[
+                    timestamp(Some(locked_until)),
+                    integer(id),
+                    timestamp(Some(now)),
+                ],
+            )?;
+            Ok(db.change_count() > 0)
+        })?)
+    }
+
+    fn finish_job(&self, id: u64) -> Result {
+        Ok(self.execute("DELETE FROM ScheduledJobs WHERE id = ?", &[integer(id)])?)
+    }
+
+    fn reschedule_job(&self, id: u64, run_at: OffsetDateTime, error: Option<&str>) -> Result {
+        Ok(match error {
+            Some(error) => self.execute(
+                "UPDATE ScheduledJobs SET run_at = ?, last_error = ?, locked_until = NULL \
+                WHERE id = ?",
+                &[
+                    timestamp(Some(run_at)),
+                    Value::String(error.to_string()),
+                    integer(id),
+                ],
+            ),
+            None => self.execute(
+                "UPDATE ScheduledJobs SET run_at = ?, attempts = 0, last_error = NULL, \
+                locked_until = NULL WHERE id = ?",
+                &[timestamp(Some(run_at)), integer(id)],
+            ),
+        }?)
+    }
+
+    fn fail_job(&self, id: u64, error: &str) -> Result {
+        Ok(self.execute(
+            "UPDATE ScheduledJobs SET failed = 1, last_error = ?, locked_until = NULL WHERE id = ?",
+            &[Value::String(error.to_string()), integer(id)],
+        )?)
+    }
+
+    fn fetch_jobs(&self) -> Result<Vec<ScheduledJob>> {
+        Ok(self.fetch(
+            "SELECT id, payload, run_at, attempts, last_error, locked_until, failed FROM ScheduledJobs ORDER BY run_at, id",
+            &[],
+        )?)
+    }
+
+    fn retry_job(&self, id: u64, now: OffsetDateTime) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "UPDATE ScheduledJobs SET failed = 0, attempts = 0, run_at = ?, locked_until = NULL \
+                WHERE id = ? AND (locked_until IS NULL OR locked_until <= ?)",
+                &[timestamp(Some(now)), integer(id), timestamp(Some(now))],
+            )?;
+            Ok(db.change_count() > 0)
+        })?)
+    }
+
+    fn cancel_job(&self, id: u64) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "DELETE FROM ScheduledJobs WHERE id = ?",
+                &[integer(id)],
+            )?;
+            Ok(db.change_count() > 0)
+        })?)
+    }
+
+    fn link_minecraft_account(
+        &self,
+        user: u64,
+        ign: &str,
+        uuid: Option<&str>,
+        linked_by: u64,
+        linked_at: OffsetDateTime,
+    ) -> Result<bool> {
+        let uuid = uuid.map_or(Value::Null, |uuid| Value::String(uuid.to_string()));
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "INSERT INTO MinecraftAccounts (user, ign, uuid, linked_by, linked_at) \
+                values (?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+                &[
+                    integer(user),
+                    Value::String(ign.to_string()),
+                    uuid.clone(),
+                    integer(linked_by),
+                    timestamp(Some(linked_at)),
+                ],
+            )?;
+            let inserted = db.change_count() > 0;
+            execute(
+                &db,
+                "UPDATE MinecraftAccounts SET uuid = ? \
+                WHERE user = ? AND lower(ign) = lower(?) AND uuid IS NULL",
+                &[uuid, integer(user), Value::String(ign.to_string())],
+            )?;
+            Ok(inserted)
+        })?)
+    }
+
+    fn unlink_minecraft_account(&self, user: u64, ign: &str) -> Result<bool> {
+        Ok(self.get_lock(|db| {
+            execute(
+                &db,
+                "DELETE FROM MinecraftAccounts WHERE user = ? AND lower(ign) = lower(?)",
+                &[integer(user), Value::String(ign.to_string())],
+            )?;
+            Ok(db.change_count() > 0)
+        })?)
+    }
+
+    fn fetch_minecraft_accounts(&self, user: u64) -> Result<Vec<MinecraftAccount>> {
+        Ok(self.fetch(
+            "SELECT user, ign, uuid, linked_by, linked_at FROM MinecraftAccounts \
+            WHERE user = ? ORDER BY linked_at, rowid",
+            &[integer(user)],
+        )?)
+    }
+
+    fn fetch_minecraft_account_users(
+        &self,
+        ign: &str,
+        uuid: Option<&str>,
+    ) -> Result<Vec<MinecraftAccount>> {
+        Ok(self.fetch(
+            "SELECT user, ign, uuid, linked_by, linked_at FROM MinecraftAccounts \
+            WHERE lower(ign) = lower(?) OR uuid = ? ORDER BY linked_at, rowid",
+            &[
+                Value::String(ign.to_string()),
+                uuid.map_or(Value::Null, |uuid| Value::String(uuid.to_string())),
+            ],
+        )?)
+    }
+
+    fn fetch_banned_accounts(&self, now: OffsetDateTime) -> Result<Vec<KnownAccount>> {
+        Ok(self.fetch(
+            "SELECT k.id, k.name, k.avatar, k.invite FROM KnownAccounts k \
+            JOIN ScheduledScrimUnbans s ON s.id = k.id \
+            WHERE s.logged = 0 AND (s.time IS NULL OR s.time > ?)",
+            &[timestamp(Some(now))],
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_rows_are_skipped() {
+        let db = SqliteDatabase::open(":memory:").unwrap();
+        db.get_lock(|conn| {
+            conn.execute(
+                "
+                insert into ScheduledScrimUnbans values (1, 1700000000, '2,3', 0);
+                insert into ScheduledScrimUnbans values (2, 1700000000, 'not,ids', 0);
+                insert into ScheduledScrimUnbans values (3, 'soon', '', 0);
+                insert into Freezes (id, roles, time) values (4, '5', NULL);
+                ",
+            )
+        })
+        .unwrap();
+
+        let unbans = db.fetch_scrim_unbans().unwrap();
+        assert_eq!(unbans.iter().map(|u| u.id).collect::<Vec<_>>(), vec![1]);
+        assert!(db.fetch_freezes_for(4).unwrap().is_none());
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+
+use serenity::{model::prelude::*, prelude::*};
+use time::OffsetDateTime;
+
+use crate::consts::{CONFIG, DATABASE};
+use crate::db::KnownAccount;
+use crate::Result;
+
+/// Names at least this similar (0 to 1) count as a match
+const NAME_SIMILARITY: f64 = 0.75;
+/// Shorter names are too common to say anything about who is behind them
+const MIN_NAME_LENGTH: usize = 3;
+/// Sharing an invite with more uses than this means nothing, it's probably a public invite
+const SHARED_INVITE_MAX_USES: u64 = 10;
+/// How many suspects are shown on an alert
+const MAX_SUSPECTS: usize = 3;
+
+lazy_static::lazy_static! {
+    /// How often every invite was used when it was last checked, to find the invite a member
+    /// joined with
+    static ref INVITE_USES: Mutex<HashMap<String, u64>> = Mutex::new(HashMap::new());
+}
+
+/// The invite a member joined with
+#[derive(Clone, Debug)]
+pub struct UsedInvite {
+    pub code: String,
+    pub inviter: Option<UserId>,
+    pub uses: u64,
+}
+
+/// The parts of a new member that are compared against banned users
+pub struct Joiner<'a> {
+    pub id: UserId,
+    pub name: &'a str,
+    pub avatar: Option<&'a str>,
+    pub invite: Option<&'a UsedInvite>,
+    pub account_age: time::Duration,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Signal {
+    /// How similar the names are, from 0 to 1
+    SimilarName(f64),
+    SameAvatar,
+    /// The banned user created the invite
+    InvitedBy,
+    SharedInvite,
+    /// How old the account is in days
+    NewAccount(i64),
+}
+
+impl Signal {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::SimilarName(similarity) => {
+                format!("Similar name ({:.0}%)", similarity * 100.0)
+            }
+            Self::SameAvatar => String::from("Same avatar"),
+            Self::InvitedBy => String::from("Joined through their invite"),
+            Self::SharedInvite => String::from("Joined through the same invite"),
+            Self::NewAccount(days) => format!("Account is {} day(s) old", days),
+        }
+    }
+}
+
+/// A banned user the new member might be an alt of
+#[derive(Debug)]
+pub struct Suspect {
+    pub banned: UserId,
+    pub name: String,
+    pub score: u32,
+    pub signals: Vec<Signal>,
+}
+
+/// Only lowercase letters and digits are compared, so `xX_Name_Xx` and `name` are close
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// How similar two names are, from 0 (nothing in common) to 1 (the same)
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let a = normalize(a).chars().collect::<Vec<_>>();
+    let b = normalize(b).chars().collect::<Vec<_>>();
+    if a.len().min(b.len()) < MIN_NAME_LENGTH {
+        return 0.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / a.len().max(b.len()) as f64
+}
+
+/// Scores how likely the new member is an alt of the banned user, out of 100
+pub fn score(joiner: &Joiner, banned: &KnownAccount) -> Suspect {
+    let mut score = 0;
+    let mut signals = Vec::new();
+
+    let similarity = name_similarity(joiner.name, &banned.name);
+    if similarity >= NAME_SIMILARITY {
+        // 20 for a barely similar name up to 40 for the same name
+        score += 20 + ((similarity - NAME_SIMILARITY) * 80.0).round() as u32;
+        signals.push(Signal::SimilarName(similarity));
+    }
+
+    if joiner.avatar.is_some() && joiner.avatar == banned.avatar.as_deref() {
+        score += 40;
+        signals.push(Signal::SameAvatar);
+    }
+
+    if let Some(invite) = joiner.invite {
+        if invite.inviter == Some(UserId(banned.id)) {
+            score += 35;
+            signals.push(Signal::InvitedBy);
+        } else if invite.uses <= SHARED_INVITE_MAX_USES
+            && banned.invite.as_deref() == Some(invite.code.as_str())
+        {
+            score += 25;
+            signals.push(Signal::SharedInvite);
+        }
+    }
+
+    // New accounts are normal, so the age only adds to something else that matched
+    if !signals.is_empty() {
+        let days = joiner.account_age.whole_days();
+        let bonus = match days {
+            d if d < 1 => 20,
+            d if d < 7 => 15,
+            d if d < 30 => 5,
+            _ => 0,
+        };
+        if bonus > 0 {
+            score += bonus;
+            signals.push(Signal::NewAccount(days));
+        }
+    }
+
+    Suspect {
+        banned: UserId(banned.id),
+        name: banned.name.clone(),
+        score: score.min(100),
+        signals,
+    }
+}
+
+/// Every banned user scoring at least the threshold, most likely first
+pub fn suspects(joiner: &Joiner, banned: &[KnownAccount], threshold: u32) -> Vec<Suspect> {
+    let mut suspects = banned
+        .iter()
+        .filter(|account| account.id != joiner.id.0)
+        .map(|account| score(joiner, account))
+        .filter(|suspect| suspect.score >= threshold)
+        .collect::<Vec<_>>();
+    suspects.sort_by_key(|suspect| std::cmp::Reverse(suspect.score));
+    suspects
+}
+
+pub struct Evasion;
+
+impl Evasion {
+    /// Takes the first snapshot of the invite uses
+    pub fn init(ctx: &Context) {
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            if let Err(err) = Self::used_invite(&ctx).await {
+                tracing::error!("Failed to fetch invites: {}", err);
+            }
+        });
+    }
+
+    /// Finds the invite whose uses went up since the last check
+    async fn used_invite(ctx: &Context) -> Result<Option<UsedInvite>> {
+        let invites = CONFIG.guild.invites(ctx).await?;
+        let mut uses = INVITE_USES.lock().await;
+        // Without an earlier snapshot every invite looks like it was just used
+        let known = !uses.is_empty();
+        let used = invites
+            .iter()
+            .filter(|_| known)
+            .find(|invite| uses.get(&invite.code).map_or(0, |u| *u) < invite.uses)
+            .map(|invite| UsedInvite {
+                code: invite.code.clone(),
+                inviter: invite.inviter.as_ref().map(|u| u.id),
+                uses: invite.uses,
+            });
+        *uses = invites
+            .into_iter()
+            .map(|invite| (invite.code, invite.uses))
+            .collect();
+        Ok(used)
+    }
+
+    /// Remembers the new member and reports them if they look like an alt of a banned user
+    pub async fn on_join(ctx: &Context, member: &Member) -> Result<()> {
+        let invite = Self::used_invite(ctx)
+            .await
+            .map_err(|err| tracing::warn!("Failed to fetch invites: {}", err))
+            .ok()
+            .flatten();
+
+        let account = KnownAccount {
+            id: member.user.id.0,
+            name: member.user.name.clone(),
+            avatar: member.user.avatar.clone(),
+            invite: invite.as_ref().map(|invite| invite.code.clone()),
+        };
+        let now = OffsetDateTime::now_utc();
+        let banned = DATABASE
+            .run(move |db| {
+                db.record_account(&account)?;
+                db.fetch_banned_accounts(now)
+            })
+            .await?;
+
+        let created =
+            OffsetDateTime::from_unix_timestamp(member.user.created_at().unix_timestamp())?;
+        let joiner = Joiner {
+            id: member.user.id,
+            name: &member.user.name,
+            avatar: member.user.avatar.as_deref(),
+            invite: invite.as_ref(),
+            account_age: now - created,
+        };
+        let suspects = suspects(&joiner, &banned, CONFIG.evasion_threshold);
+        if suspects.is_empty() {
+            return Ok(());
+        }
+        tracing::info!(
+            "{} looks like an alt of {} (score {})",
+            member.user.tag(),
+            suspects[0].banned,
+            suspects[0].score
+        );
+
+        CONFIG
+            .evasion_alerts
+            .send_message(ctx, |msg| {
+                msg.embed(|e| {
+                    e.author(|a| {
+                        a.name(format!("{} Might Be Evading a Ban", member.user.tag()))
+                            .icon_url(member.user.face())
+                    })
+                    .color(0xFFA500)
+                    .field("User", member.mention(), true)
+                    .field("Score", format!("{}/100", suspects[0].score), true)
+                    .field(
+                        "Created",
+                        format!("<t:{}:R>", created.unix_timestamp()),
+                        true,
+                    );
+                    if let Some(invite) = &invite {
+                        e.field(
+                            "Invite",
+                            format!(
+                                "`{}`{}",
+                                invite.code,
+                                invite
+                                    .inviter
+                                    .map_or(String::new(), |u| format!(" by {}", u.mention()))
+                            ),
+                            true,
+                        );
+                    }
+                    for suspect in suspects.iter().take(MAX_SUSPECTS) {
+                        e.field(
+                            format!("Alt of {}? ({}/100)", suspect.name, suspect.score),
+                            format!(
+                                "{}\n{}",
+                                suspect.banned.mention(),
+                                suspect
+                                    .signals
+                                    .iter()
+                                    .map(|s| format!("• {}", s.describe()))
+                                    .collect::<Vec<_>>()
+                                    .join("\n")
+                            ),
+                            false,
+                        );
+                    }
+                    e
+                })
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        for suspect in suspects.iter().take(MAX_SUSPECTS) {
+                            row.create_button(|b| {
+                                b.custom_id(format!(
+                                    "evasion:{}:{}",
+                                    member.user.id, suspect.banned
+                                ))
+                                .label(format!("Ban as alt of {}", suspect.name))
+                                .style(component::ButtonStyle::Danger)
+                            });
+                        }
+                        row
+                    })
+                })
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn banned(name: &str, avatar: Option<&str>, invite: Option<&str>) -> KnownAccount {
+        KnownAccount {
+            id: 1,
+            name: name.to_string(),
+            avatar: avatar.map(String::from),
+            invite: invite.map(String::from),
+        }
+    }
+
+    fn joiner<'a>(
+        name: &'a str,
+        avatar: Option<&'a str>,
+        invite: Option<&'a UsedInvite>,
+        days: i64,
+    ) -> Joiner<'a> {
+        Joiner {
+            id: UserId(2),
+            name,
+            avatar,
+            invite,
+            account_age: time::Duration::days(days),
+        }
+    }
+
+    #[test]
+    fn similarity() {
+        assert_eq!(name_similarity("Cheater", "cheater"), 1.0);
+        assert_eq!(name_similarity("xX_Cheater_Xx", "xxcheaterxx"), 1.0);
+        assert!(name_similarity("cheater", "cheater2") >= NAME_SIMILARITY);
+        assert!(name_similarity("cheater", "chaeter") >= 0.7);
+        assert!(name_similarity("cheater", "bridger") < NAME_SIMILARITY);
+        // too short to compare
+        assert_eq!(name_similarity("ab", "ab"), 0.0);
+        assert_eq!(levenshtein(&['a', 'b', 'c'], &[]), 3);
+    }
+
+    #[test]
+    fn unrelated_accounts_score_nothing() {
+        let suspect = score(
+            &joiner("someone", Some("aaa"), None, 0),
+            &banned("cheater", Some("bbb"), None),
+        );
+        assert_eq!(suspect.score, 0);
+        // a new account on its own is not suspicious
+        assert!(suspect.signals.is_empty());
+    }
+
+    #[test]
+    fn signals_add_up() {
+        let account = banned("cheater", Some("aaa"), Some("abc"));
+        let name_only = score(&joiner("cheater", None, None, 365), &account);
+        assert_eq!(name_only.score, 40);
+
+        let with_avatar = score(&joiner("cheater", Some("aaa"), None, 365), &account);
+        assert_eq!(with_avatar.score, 80);
+        assert_eq!(
+            with_avatar.signals,
+            vec![Signal::SimilarName(1.0), Signal::SameAvatar]
+        );
+
+        let fresh = score(&joiner("cheater", Some("aaa"), None, 0), &account);
+        assert_eq!(fresh.score, 100);
+    }
+
+    #[test]
+    fn invites() {
+        let account = banned("cheater", None, Some("abc"));
+        let shared = UsedInvite {
+            code: String::from("abc"),
+            inviter: None,
+            uses: 3,
+        };
+        let suspect = score(&joiner("someone", None, Some(&shared), 2), &account);
+        assert_eq!(
+            suspect.signals,
+            vec![Signal::SharedInvite, Signal::NewAccount(2)]
+        );
+        assert_eq!(suspect.score, 40);
+
+        let public = UsedInvite {
+            uses: 500,
+            ..shared.clone()
+        };
+        assert_eq!(
+            score(&joiner("someone", None, Some(&public), 2), &account).score,
+            0
+        );
+
+        let theirs = UsedInvite {
+            code: String::from("xyz"),
+            inviter: Some(UserId(1)),
+            uses: 500,
+        };
+        let suspect = score(&joiner("someone", None, Some(&theirs), 365), &account);
+        assert_eq!(suspect.signals, vec![Signal::InvitedBy]);
+        assert_eq!(suspect.score, 35);
+    }
+
+    #[test]
+    fn suspects_are_ranked() {
+        let mut close = banned("cheater", Some("aaa"), None);
+        close.id = 3;
+        let accounts = vec![
+            banned("cheater", None, None),
+            close,
+            banned("someone", None, None),
+            // the member's own account
+            KnownAccount {
+                id: 2,
+                ..banned("cheater", Some("aaa"), None)
+            },
+        ];
+        let found = suspects(&joiner("cheater", Some("aaa"), None, 365), &accounts, 40);
+        assert_eq!(
+            found.iter().map(|s| s.banned).collect::<Vec<_>>(),
+            vec![UserId(3), UserId(1)]
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashSet;
+
+use serenity::{model::prelude::*, prelude::*};
+use time::OffsetDateTime;
+
+use crate::commands::screenshare::ban::{expiry, scrim_ban, unfreeze_before_ban, BanLength};
+use crate::commands::screenshare::close::transcript;
+use crate::commands::screenshare::screenshare::ALLOW_PERMS;
+use crate::consts::{CONFIG, DATABASE};
+use crate::db::{Freeze, Ids, Job};
+use crate::features::scheduler::Scheduler;
+use crate::Result;
+
+/// How long the channels of a freeze stay after it ended, so the last messages can still be read
+const CLEANUP_DELAY: time::Duration = time::Duration::minutes(1);
+
+/// Whether the user let the deadline of the freeze from `frozen_at` pass. Having admitted or being
+/// in the voice channel of the freeze when it passes counts as complying.
+fn missed_deadline(freeze: Option<&Freeze>, frozen_at: i64, in_voice: bool) -> bool {
+    // They got unfrozen in time, or unfrozen and frozen again which has a deadline of its own
+    let still_frozen = freeze.map_or(false, |freeze| {
+        freeze.time.unix_timestamp() == frozen_at && freeze.admitted_at.is_none()
+    });
+    still_frozen && !in_voice
+}
+
+/// The channel the freeze is talked about in. Freezes from before every freeze had its own
+/// channels use the shared ones.
+pub fn text_channel(freeze: &Freeze) -> ChannelId {
+    freeze.text_channel.unwrap_or(CONFIG.frozen_chat)
+}
+
+/// The channel the frozen user is screenshared in
+pub fn voice_channel(freeze: &Freeze) -> ChannelId {
+    freeze.voice_channel.unwrap_or(CONFIG.hello_cheaters)
+}
+
+/// Freezes the user again after their ban failed, with the freeze they had before
+pub async fn refreeze(ctx: &Context, freeze: Freeze) -> Result<()> {
+    let member = CONFIG.guild.member(&ctx, freeze.id).await?;
+    let mut new_roles = member
+        .roles(ctx)
+        .unwrap_or_default()
+        .iter()
+        .filter(|r| r.managed)
+        .map(|r| r.id)
+        .collect::<Vec<_>>();
+    new_roles.push(CONFIG.frozen);
+    member.edit(&ctx, |m| m.roles(new_roles)).await?;
+
+    DATABASE
+        .run(move |db| {
+            let roles = Ids(freeze.roles.iter().map(|r| r.0).collect());
+            db.add_freeze(freeze.id, roles, freeze.time)?;
+            if let Some(admitted_at) = freeze.admitted_at {
+                db.admit_freeze(freeze.id, admitted_at)?;
+            }
+            if let (Some(text), Some(voice)) = (freeze.text_channel, freeze.voice_channel) {
+                db.set_freeze_channels(freeze.id, text.0, voice.0)?;
+            }
+            Ok(())
+        })
+        .await?;
+    Ok(())
+}
+
+/// Enforces the deadline frozen users have to admit or get screenshared, and punishes users that
+/// leave the server while frozen. Every freeze happens in its own text and voice channel, which
+/// only the frozen user and screensharers can see.
+pub struct Frozen;
+
+impl Frozen {
+    /// When a user frozen at `frozen_at` has to have admitted or joined their voice channel
+    pub fn deadline_for(frozen_at: OffsetDateTime) -> OffsetDateTime {
+        frozen_at + time::Duration::minutes(CONFIG.freeze_deadline as i64)
+    }
+
+    pub async fn schedule_deadline(user: UserId, frozen_at: OffsetDateTime) -> Result<()> {
+        let job = Job::FreezeDeadline {
+            user: user.0,
+            frozen_at: frozen_at.unix_timestamp(),
+        };
+        Scheduler::schedule(None, job, Self::deadline_for(frozen_at)).await?;
+        Ok(())
+    }
+
+    /// Creates the text and voice channel of a freeze
+    pub async fn create_channels(ctx: &Context, user: &User) -> Result<(ChannelId, ChannelId)> {
+        let overwrites = |allow: Permissions| {
+            [
+                PermissionOverwriteType::Member(user.id),
+                PermissionOverwriteType::Role(CONFIG.ss_support),
+            ]
+            .into_iter()
+            .map(move |kind| PermissionOverwrite {
+                allow,
+                deny: Permissions::empty(),
+                kind,
+            })
+            .chain(std::iter::once(PermissionOverwrite {
+                allow: Permissions::empty(),
+                deny: Permissions::VIEW_CHANNEL,
+                kind: PermissionOverwriteType::Role(CONFIG.guild.0.into()),
+            }))
+            .collect::<Vec<_>>()
+        };
+
+        let text = CONFIG
+            .guild
+            .create_channel(&ctx, |ch| {
+                if let Some(category) = CONFIG.freeze_category {
+                    ch.category(category);
+                }
+                ch.name(format!("frozen-{}", user.name))
+                    .kind(ChannelType::Text)
+                    .permissions(overwrites(*ALLOW_PERMS | Permissions::ATTACH_FILES))
+            })
+            .await?;
+        let voice = CONFIG
+            .guild
+            .create_channel(&ctx, |ch| {
+                if let Some(category) = CONFIG.freeze_category {
+                    ch.category(category);
+                }
+                ch.name(format!("Frozen: {}", user.name))
+                    .kind(ChannelType::Voice)
+                    .permissions(overwrites(
+                        Permissions::VIEW_CHANNEL | Permissions::CONNECT | Permissions::SPEAK,
+                    ))
+            })
+            .await;
+        match voice {
+            Ok(voice) => Ok((text.id, voice.id)),
+            Err(err) => {
+                let _ = text.delete(&ctx).await;
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Archives and deletes the channels of a freeze that just ended, after a short delay
+    pub async fn schedule_cleanup(freeze: &Freeze) -> Result<()> {
+        if let (Some(text), Some(voice)) = (freeze.text_channel, freeze.voice_channel) {
+            let job = Job::FreezeCleanup {
+                user: freeze.id,
+                text: text.0,
+                voice: voice.0,
+            };
+            Scheduler::schedule(None, job, OffsetDateTime::now_utc() + CLEANUP_DELAY).await?;
+        }
+        Ok(())
+    }
+
+    /// Logs the transcript of the text channel of an ended freeze and deletes both its channels.
+    /// Channels that are already gone are skipped, so it can be retried.
+    pub async fn cleanup(
+        ctx: &Context,
+        user: UserId,
+        text: ChannelId,
+        voice: ChannelId,
+    ) -> Result<()> {
+        // Freezes that are put back after a failed ban keep their channels
+        let refrozen = DATABASE
+            .run(move |db| db.fetch_freezes_for(user.0))
+            .await?
+            .map_or(false, |freeze| freeze.text_channel == Some(text));
+        if refrozen {
+            return Ok(());
+        }
+
+        if text.to_channel_cached(&ctx.cache).is_some() {
+            let transcript = transcript(ctx, text).await?;
+            let ban = DATABASE
+                .run(|db| db.fetch_scrim_unbans())
+                .await?
+                .into_iter()
+                .find(|ban| ban.id == user.0 && !ban.is_expired());
+            CONFIG
+                .ss_logs
+                .send_message(&ctx, |msg| {
+                    msg.add_file(AttachmentType::Bytes {
+                        data: transcript.into(),
+                        filename: format!("freeze-{}.zip", text),
+                    })
+                    .embed(|e| {
+                        e.title("Freeze ended")
+                            .description(format!("- User: {}", user.mention()))
+                            .field(
+                                "Result",
+                                ban.map_or(String::from("Unfrozen"), |ban| {
+                                    format!("Scrim banned until {}", expiry(ban.date))
+                                }),
+                                true,
+                            )
+                    })
+                })
+                .await?;
+            text.delete(&ctx).await?;
+        }
+        if voice.to_channel_cached(&ctx.cache).is_some() {
+            voice.delete(&ctx).await?;
+        }
+        Ok(())
+    }
+
+    /// Runs once the deadline of a freeze passed
+    pub async fn deadline(ctx: &Context, user: UserId, frozen_at: i64) -> Result<()> {
+        let freeze = match DATABASE.run(move |db| db.fetch_freezes_for(user.0)).await? {
+            Some(freeze) => freeze,
+            None => return Ok(()),
+        };
+        let voice = voice_channel(&freeze);
+        let in_voice = ctx
+            .cache
+            .guild(CONFIG.guild)
+            .and_then(|guild| guild.voice_states.get(&user).and_then(|v| v.channel_id))
+            == Some(voice);
+        if !missed_deadline(Some(&freeze), frozen_at, in_voice) {
+            return Ok(());
+        }
+
+        let mut content = format!(
+            "{} {} did not admit or join {} within {} minutes of being frozen.",
+            CONFIG.ss_support.mention(),
+            user.mention(),
+            voice.mention(),
+            CONFIG.freeze_deadline
+        );
+        let channel = text_channel(&freeze);
+        if CONFIG.freeze_deadline_ban {
+            let reason = String::from("Refused to screenshare");
+            if CONFIG.guild.member(&ctx, user).await.is_ok() {
+                let (unban_date, tier) = BanLength::Escalated.resolve(user).await?;
+                unfreeze_before_ban(ctx, user).await?;
+                let ban = scrim_ban(
+                    ctx,
+                    user,
+                    ctx.cache.current_user_id(),
+                    unban_date,
+                    reason,
+                    tier,
+                )
+                .await;
+                // Without the freeze the retry would find nothing to punish
+                if let Err(err) = ban {
+                    tracing::error!(
+                        "Failed to ban {} after their freeze deadline: {}",
+                        user,
+                        err
+                    );
+                    refreeze(ctx, freeze).await?;
+                    return Err(err);
+                }
+            } else {
+                // They left without being banned for it, so they can't be unfrozen first
+                Self::ban_absent(ctx, user, &freeze, reason).await?;
+            }
+            content.push_str(" They were banned for refusing to screenshare.");
+        }
+        tracing::info!("{} missed their freeze deadline", user);
+
+        channel
+            .send_message(&ctx, |msg| msg.content(content))
+            .await?;
+        Ok(())
+    }
+
+    /// Bans a frozen user that is no longer in the server and ends their freeze. The ban doesn't
+    /// remove any roles then, so the ones taken by the freeze are given back once it runs out.
+    async fn ban_absent(
+        ctx: &Context,
+        user: UserId,
+        freeze: &Freeze,
+        reason: String,
+    ) -> Result<()> {
+        let (unban_date, tier) = BanLength::Escalated.resolve(user).await?;
+        scrim_ban(
+            ctx,
+            user,
+            ctx.cache.current_user_id(),
+            unban_date,
+            reason,
+            tier,
+        )
+        .await?;
+        let (id, roles) = (user.0, freeze.roles.clone());
+        DATABASE
+            .run(move |db| {
+                if let Some(ban) = db.fetch_scrim_unbans()?.into_iter().find(|x| x.id == id) {
+                    let roles = ban
+                        .roles
+                        .0
+                        .iter()
+                        .chain(roles.iter().map(|r| &r.0))
+                        .copied()
+                        .collect::<HashSet<_>>();
+                    db.modify_scrim_unban(id, ban.date, &Ids(roles.into_iter().collect()))?;
+                }
+                db.remove_freeze(id)
+            })
+            .await?;
+        Self::schedule_cleanup(freeze).await
+    }
+
+    /// Runs when anyone leaves the server, bans them if they were frozen
+    pub async fn on_leave(ctx: &Context, user: &User) -> Result<()> {
+        let id = user.id.0;
+        let freeze = match DATABASE.run(move |db| db.fetch_freezes_for(id)).await? {
+            Some(freeze) => freeze,
+            None => return Ok(()),
+        };
+        // Without the ban the freeze is kept, and given back if they rejoin
+        if !CONFIG.freeze_leave_ban {
+            return Ok(());
+        }
+
+        Self::ban_absent(
+            ctx,
+            user.id,
+            &freeze,
+            String::from("Logged out while frozen"),
+        )
+        .await?;
+        tracing::info!("{} left while frozen and was banned", user.tag());
+
+        text_channel(&freeze)
+            .send_message(&ctx, |msg| {
+                msg.content(format!(
+                    "{} {} ({}) left the server while frozen and was banned for logging out.",
+                    CONFIG.ss_support.mention(),
+                    user.mention(),
+                    user.tag()
+                ))
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadlines() {
+        let frozen_at = OffsetDateTime::now_utc();
+        let freeze = |admitted_at| Freeze {
+            id: 1,
+            roles: Vec::new(),
+            time: frozen_at,
+            admitted_at,
+            text_channel: None,
+            voice_channel: None,
+        };
+        let at = frozen_at.unix_timestamp();
+
+        assert!(missed_deadline(Some(&freeze(None)), at, false));
+        assert!(!missed_deadline(Some(&freeze(None)), at, true));
+        assert!(!missed_deadline(Some(&freeze(Some(frozen_at))), at, false));
+        assert!(!missed_deadline(None, at, false));
+        // frozen again after the freeze the deadline was for
+        assert!(!missed_deadline(Some(&freeze(None)), at - 60, false));
+    }
+}
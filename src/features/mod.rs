@@ -1 +1,4 @@
-pub mod expanding_channels;
+pub mod evasion;
+pub mod expanding_channels;
+pub mod frozen;
+pub mod scheduler;
@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use serenity::{model::prelude::*, prelude::*};
+use time::OffsetDateTime;
+
+use crate::commands::screenshare::{screenshare, unban};
+use crate::consts::DATABASE;
+use crate::db::{Job, ScheduledJob};
+use crate::features::frozen::Frozen;
+use crate::Result;
+
+/// How often the database is checked for jobs that are due
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a job may take before it's assumed the bot stopped while running it
+const LEASE: time::Duration = time::Duration::minutes(10);
+/// One-off jobs are given up on after failing this often, repeating jobs are never given up on
+const MAX_ATTEMPTS: u64 = 8;
+/// Jobs that always exist, scheduled under their own name
+const REPEATING: [Job; 3] = [Job::ScrimUnbans, Job::RefreshReactions, Job::PruneCooldowns];
+
+/// Repeating jobs are only scheduled on startup, cancelling one stops it until the bot restarts
+pub fn is_repeating(job: &Job) -> bool {
+    REPEATING.contains(job)
+}
+
+/// How often the job runs, None if it only runs once
+fn interval(job: &Job) -> Option<time::Duration> {
+    match job {
+        Job::ScrimUnbans => Some(time::Duration::minutes(3)),
+        Job::RefreshReactions => Some(time::Duration::hours(2)),
+        Job::PruneCooldowns => Some(time::Duration::minutes(10)),
+        Job::TicketTimeout { .. } | Job::FreezeDeadline { .. } | Job::FreezeCleanup { .. } => None,
+    }
+}
+
+/// How long to wait before retrying a job that failed `attempts` times in a row
+fn backoff(attempts: u64) -> time::Duration {
+    let exponent = attempts.saturating_sub(1).min(7) as u32;
+    (time::Duration::seconds(30) * 2i32.pow(exponent)).min(time::Duration::hours(1))
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Finish,
+    Reschedule(OffsetDateTime),
+    Retry(OffsetDateTime),
+    GiveUp,
+}
+
+/// What happens to a job after it ran, `attempts` counting the run that just happened
+fn outcome(job: &Job, attempts: u64, succeeded: bool, now: OffsetDateTime) -> Outcome {
+    match (interval(job), succeeded) {
+        (Some(interval), true) => Outcome::Reschedule(now + interval),
+        (None, true) => Outcome::Finish,
+        // A failing repeating job still runs at least as often as it normally would
+        (Some(interval), false) => Outcome::Retry(now + backoff(attempts).min(interval)),
+        (None, false) if attempts >= MAX_ATTEMPTS => Outcome::GiveUp,
+        (None, false) => Outcome::Retry(now + backoff(attempts)),
+    }
+}
+
+/// Runs the jobs in the `ScheduledJobs` table once they are due. A job is claimed before it runs,
+/// so if the bot stops halfway it runs again once the claim runs out.
+pub struct Scheduler;
+
+impl Scheduler {
+    pub fn init(ctx: &Context) {
+        tokio::spawn(Self::run(ctx.clone()));
+    }
+
+    /// Schedules a job. If a job with the same key is already scheduled, that one is kept.
+    pub async fn schedule(key: Option<String>, job: Job, run_at: OffsetDateTime) -> Result<bool> {
+        Ok(DATABASE
+            .run(move |db| db.schedule_job(key.as_deref(), &job, run_at))
+            .await?)
+    }
+
+    async fn run(ctx: Context) {
+        let now = OffsetDateTime::now_utc();
+        for job in REPEATING {
+            let _ = Self::schedule(Some(job.name().to_string()), job, now)
+                .await
+                .map_err(|err| tracing::error!("Failed to schedule repeating job: {}", err));
+        }
+
+        loop {
+            let now = OffsetDateTime::now_utc();
+            match DATABASE.run(move |db| db.fetch_due_jobs(now)).await {
+                Ok(jobs) => {
+                    for job in jobs {
+                        tokio::spawn(Self::start(ctx.clone(), job));
+                    }
+                }
+                Err(err) => tracing::error!("Failed to fetch due jobs: {}", err),
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn start(ctx: Context, job: ScheduledJob) {
+        let id = job.id;
+        let now = OffsetDateTime::now_utc();
+        match DATABASE
+            .run(move |db| db.claim_job(id, now, now + LEASE))
+            .await
+        {
+            Ok(true) => {}
+            // Still running from an earlier poll
+            Ok(false) => return,
+            Err(err) => {
+                tracing::error!("Failed to claim job {}: {}", id, err);
+                return;
+            }
+        }
+
+        let result = execute(&ctx, &job.job).await;
+        if let Err(ref err) = result {
+            tracing::error!("Job {} ({}) failed: {}", id, job.job.name(), err);
+        }
+        let error = result.err().map(|err| err.to_string());
+        let now = OffsetDateTime::now_utc();
+        let res = match outcome(&job.job, job.attempts + 1, error.is_none(), now) {
+            Outcome::Finish => DATABASE.run(move |db| db.finish_job(id)).await,
+            Outcome::Reschedule(at) => {
+                DATABASE
+                    .run(move |db| db.reschedule_job(id, at, None))
+                    .await
+            }
+            Outcome::Retry(at) => {
+                DATABASE
+                    .run(move |db| db.reschedule_job(id, at, error.as_deref()))
+                    .await
+            }
+            Outcome::GiveUp => {
+                tracing::warn!("Giving up on job {} ({})", id, job.job.name());
+                DATABASE
+                    .run(move |db| db.fail_job(id, &error.unwrap_or_default()))
+                    .await
+            }
+        };
+        if let Err(err) = res {
+            tracing::error!("Failed to update job {}: {}", id, err);
+        }
+    }
+}
+
+async fn execute(ctx: &Context, job: &Job) -> Result<()> {
+    match *job {
+        Job::ScrimUnbans => unban::unban_expired(ctx).await,
+        Job::TicketTimeout {
+            channel,
+            in_question,
+            creator,
+        } => {
+            screenshare::ticket_timeout(
+                ctx,
+                UserId(in_question),
+                UserId(creator),
+                ChannelId(channel),
+            )
+            .await
+        }
+        Job::RefreshReactions => crate::handler::reload_reactions().await,
+        Job::PruneCooldowns => {
+            bridge_scrims::cooldown::prune_all().await;
+            Ok(())
+        }
+        Job::FreezeDeadline { user, frozen_at } => {
+            Frozen::deadline(ctx, UserId(user), frozen_at).await
+        }
+        Job::FreezeCleanup { user, text, voice } => {
+            Frozen::cleanup(ctx, UserId(user), ChannelId(text), ChannelId(voice)).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_exponentially() {
+        assert_eq!(backoff(1), time::Duration::seconds(30));
+        assert_eq!(backoff(2), time::Duration::minutes(1));
+        assert_eq!(backoff(4), time::Duration::minutes(4));
+        assert_eq!(backoff(8), time::Duration::hours(1));
+        assert_eq!(backoff(100), time::Duration::hours(1));
+    }
+
+    #[test]
+    fn outcomes() {
+        let now = OffsetDateTime::now_utc();
+        let timeout = Job::TicketTimeout {
+            channel: 1,
+            in_question: 2,
+            creator: 3,
+        };
+        assert_eq!(outcome(&timeout, 3, true, now), Outcome::Finish);
+        assert_eq!(
+            outcome(&timeout, 3, false, now),
+            Outcome::Retry(now + time::Duration::minutes(2))
+        );
+        assert_eq!(outcome(&timeout, MAX_ATTEMPTS, false, now), Outcome::GiveUp);
+        assert_eq!(
+            outcome(&Job::ScrimUnbans, 1, true, now),
+            Outcome::Reschedule(now + time::Duration::minutes(3))
+        );
+        // repeating jobs never give up and don't wait longer than they normally would
+        assert_eq!(
+            outcome(&Job::ScrimUnbans, 50, false, now),
+            Outcome::Retry(now + time::Duration::minutes(3))
+        );
+    }
+}
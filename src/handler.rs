@@ -1,5 +1,4 @@
 use std::error::Error;
-use std::time::Duration;
 use std::{collections::HashMap, collections::HashSet, sync::Arc};
 
 use lazy_static::lazy_static;
@@ -20,12 +19,17 @@ use crate::commands::screenshare::unban::scrim_unban;
 use crate::consts::CONFIG;
 use crate::consts::DATABASE as database;
 use crate::db::{CustomReaction, Ids};
+use crate::features::evasion::Evasion;
 use crate::features::expanding_channels::ExpandingChannels;
+use crate::features::frozen::Frozen;
+use crate::features::scheduler::Scheduler;
 
 lazy_static! {
     pub static ref HANDLERS: Vec<Box<dyn InteractionHandler>> = vec![
         commands::council::Council::new(),
         commands::notes::Notes::new(),
+        commands::link::Link::new(),
+        commands::lookup::Lookup::new(),
         commands::prefabs::Prefab::new(),
         commands::teams::TeamsCommand::new(),
         commands::captains::CaptainsCommand::new(),
@@ -37,13 +41,24 @@ lazy_static! {
         commands::ping::Ping::new(),
         commands::screenshare::ban::ScrimBan::new(),
         commands::screenshare::unban::ScrimUnban::new(),
+        commands::screenshare::bulk::BulkScrimBan::new(),
         commands::screenshare::screenshare::Screenshare::new(),
         commands::screenshare::close::Close::new(),
+        commands::screenshare::queue::Queue::new(),
         commands::screenshare::freeze::Freeze::new(),
         commands::screenshare::unfreeze::Unfreeze::new(),
+        commands::screenshare::admit::Admit::new(),
+        commands::screenshare::admit::ConfirmAdmission::new(),
         commands::screenshare::ticket::Ticket::new(),
         commands::screenshare::list_bans::ListBans::new(),
+        commands::screenshare::ban_history::BanHistory::new(),
+        commands::screenshare::appeal::Appeal::new(),
+        commands::screenshare::appeal::AppealReview::new(),
+        commands::screenshare::appeal::AppealReduce::new(),
+        commands::screenshare::evasion::Evasion::new(),
         commands::screenshare::screensharers::Screensharers::new(),
+        commands::screenshare::stats::SsStats::new(),
+        commands::jobs::Jobs::new(),
     ];
     pub static ref REACTIONS: Arc<Mutex<HashMap<String, CustomReaction>>> =
         Arc::new(Mutex::new(HashMap::new()));
@@ -73,8 +88,10 @@ impl EventHandler for Handler {
             *init = true;
 
             tokio::spawn(register_commands(ctx.clone()));
-            tokio::spawn(update_reactions_loop());
+            tokio::spawn(update_reactions_map());
             ExpandingChannels::init(&ctx);
+            Evasion::init(&ctx);
+            Scheduler::init(&ctx);
 
             for handler in &*HANDLERS {
                 handler.init(&ctx).await;
@@ -122,6 +139,18 @@ impl EventHandler for Handler {
                 }
             }
         }
+
+        if let Interaction::ModalSubmit(interaction) = &interaction {
+            let mut args = interaction.data.custom_id.split(':').collect::<Vec<_>>();
+            let name = args.drain(..1).next();
+            if let Some(name) = name {
+                if let Some(handler) = HANDLERS.iter().find(|x| x.is_handler(name.to_string())) {
+                    if let Err(err) = handler.on_modal(&ctx, interaction, &args).await {
+                        tracing::error!("{} modal failed: {}", handler.name(), err);
+                    }
+                }
+            }
+        }
     }
 
     async fn message(&self, ctx: Context, msg: Message) {
@@ -263,7 +292,11 @@ impl EventHandler for Handler {
                         .to_ascii_lowercase()
                         .contains("invalid form body")
                 {
-                    if let Err(err) = database.remove_custom_reaction(reaction.user) {
+                    let user = reaction.user;
+                    if let Err(err) = database
+                        .run(move |db| db.remove_custom_reaction(user))
+                        .await
+                    {
                         tracing::error!("Error in removal of reaction: {}", err);
                     }
                     if let Err(err) = msg.reply(&ctx, format!(
@@ -288,18 +321,33 @@ impl EventHandler for Handler {
         if let Err(err) = CONFIG.member_count.update(&ctx, member.guild_id).await {
             tracing::error!("Error when updating member count: {}", err)
         }
+
+        if member.guild_id == CONFIG.guild && !member.user.bot {
+            if let Err(err) = restore_on_rejoin(&ctx, &member).await {
+                tracing::error!("Error while restoring rejoined member: {}", err);
+            }
+            if let Err(err) = Evasion::on_join(&ctx, &member).await {
+                tracing::error!("Error while checking for ban evasion: {}", err);
+            }
+        }
     }
 
     async fn guild_member_removal(
         &self,
         ctx: Context,
         guild_id: GuildId,
-        _user: User,
+        user: User,
         _optional_member: Option<Member>,
     ) {
         if let Err(err) = CONFIG.member_count.update(&ctx, guild_id).await {
             tracing::error!("Error when updating member count: {}", err)
         }
+
+        if guild_id == CONFIG.guild && !user.bot {
+            if let Err(err) = Frozen::on_leave(&ctx, &user).await {
+                tracing::error!("Error while handling frozen member leaving: {}", err);
+            }
+        }
     }
 
     async fn guild_member_update(&self, ctx: Context, _old_member: Option<Member>, member: Member) {
@@ -375,14 +423,19 @@ async fn check_booster(ctx: &Context, member: &Member) -> Result<(), Box<dyn Err
         return Ok(());
     }
 
-    let custom_reactions = database.fetch_custom_reactions_for(member.user.id.0);
+    let user = member.user.id.0;
+    let custom_reactions = database
+        .run(move |db| db.fetch_custom_reactions_for(user))
+        .await?;
     if !custom_reactions.is_empty() {
         // if the user's server boost runs out
         let mut embed = CreateEmbed::default();
         embed.title(format!("{}'s reaction has been removed", member.user.tag()));
         embed.description("No longer has booster and is not an administrator.");
 
-        database.remove_custom_reaction(member.user.id.0)?;
+        database
+            .run(move |db| db.remove_custom_reaction(user))
+            .await?;
         CONFIG
             .reaction_logs
             .send_message(&ctx, |msg| msg.set_embed(embed.clone()))
@@ -407,7 +460,9 @@ async fn check_booster(ctx: &Context, member: &Member) -> Result<(), Box<dyn Err
 }
 
 async fn check_scrim_banned(ctx: &Context, member: &Member) -> crate::Result<()> {
-    let unbans = crate::consts::DATABASE.fetch_scrim_unbans();
+    let unbans = crate::consts::DATABASE
+        .run(|db| db.fetch_scrim_unbans())
+        .await?;
     let unban = unbans.iter().find(|x| x.id == member.user.id.0);
     if let Some(unban) = unban {
         if unban.is_expired() {
@@ -442,11 +497,10 @@ async fn check_scrim_banned(ctx: &Context, member: &Member) -> crate::Result<()>
                         .into_iter()
                         .collect::<Vec<_>>();
 
-                    crate::consts::DATABASE.modify_scrim_unban(
-                        *member.user.id.as_u64(),
-                        unban.date,
-                        &Ids(all_removed),
-                    )?;
+                    let (id, date) = (member.user.id.0, unban.date);
+                    crate::consts::DATABASE
+                        .run(move |db| db.modify_scrim_unban(id, date, &Ids(all_removed)))
+                        .await?;
                 }
             }
         }
@@ -455,20 +509,80 @@ async fn check_scrim_banned(ctx: &Context, member: &Member) -> crate::Result<()>
     Ok(())
 }
 
-async fn update_reactions_loop() {
-    loop {
-        update_reactions_map().await;
-        tokio::time::sleep(Duration::from_secs(60 * 60 * 2)).await;
-    }
+/// Puts returning members back into the state they left in, since leaving resets their roles
+async fn restore_on_rejoin(ctx: &Context, member: &Member) -> crate::Result<()> {
+    let id = member.user.id.0;
+    let (freeze, ban) = crate::consts::DATABASE
+        .run(move |db| {
+            let ban = db
+                .fetch_scrim_unbans()?
+                .into_iter()
+                .find(|x| x.id == id && !x.is_expired());
+            Ok((db.fetch_freezes_for(id)?, ban))
+        })
+        .await?;
+
+    let state = if let Some(freeze) = freeze {
+        // Their roles from before the freeze are still stored, so only the frozen role is given
+        let mut new_roles = member
+            .roles(ctx)
+            .unwrap_or_default()
+            .iter()
+            .filter(|r| r.managed)
+            .map(|r| r.id)
+            .collect::<Vec<_>>();
+        new_roles.push(CONFIG.frozen);
+        member.edit(&ctx, |m| m.roles(new_roles)).await?;
+        format!(
+            "Frozen since <t:{}:R>, restored the frozen role",
+            freeze.time.unix_timestamp()
+        )
+    } else if let Some(ban) = ban {
+        check_scrim_banned(ctx, member).await?;
+        format!(
+            "Scrim banned until {}, restored the banned role",
+            crate::commands::screenshare::ban::expiry(ban.date)
+        )
+    } else {
+        // Gives back the roles of bans that ran out while the member was away
+        return check_scrim_banned(ctx, member).await;
+    };
+
+    tracing::info!("{} rejoined: {}", member.user.tag(), state);
+    CONFIG
+        .support_bans
+        .send_message(&ctx, |msg| {
+            msg.embed(|e| {
+                e.author(|a| {
+                    a.name(format!("{} Rejoined", member.user.tag()))
+                        .icon_url(member.user.face())
+                })
+                .field("User", member.mention(), true)
+                .field("State", state, false)
+                .color(0x0E87CC)
+            })
+        })
+        .await?;
+    Ok(())
 }
 
+/// Reloads the custom reactions, logging instead of returning errors
 pub async fn update_reactions_map() {
+    if let Err(err) = reload_reactions().await {
+        tracing::error!("Failed to fetch custom reactions: {}", err);
+    }
+}
+
+/// Reloads the custom reactions, also run by the scheduler every few hours
+pub async fn reload_reactions() -> crate::Result<()> {
+    let reactions = database.run(|db| db.fetch_custom_reactions()).await?;
     let mut lock = REACTIONS.lock().await;
     let mut x = HashMap::new();
-    for reaction in database.fetch_custom_reactions() {
+    for reaction in reactions {
         x.insert(reaction.trigger.to_ascii_lowercase(), reaction);
     }
     *lock = x;
+    Ok(())
 }
 
 pub async fn register_commands(ctx: Context) -> Result<(), String> {
@@ -1,9 +1,28 @@
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::time::{Duration, SystemTime};
 
 use serenity::model::id::UserId;
 use tokio::sync::Mutex;
-use tokio::time::sleep;
+
+lazy_static::lazy_static! {
+    /// Every set of cooldowns, so expired ones can be pruned from one place
+    static ref REGISTRY: std::sync::Mutex<Vec<Weak<Mutex<Vec<Cooldown>>>>> = Default::default();
+}
+
+/// Forgets the expired cooldowns of every set of cooldowns
+pub async fn prune_all() {
+    let all = {
+        let mut registry = REGISTRY.lock().unwrap();
+        registry.retain(|cooldowns| cooldowns.strong_count() > 0);
+        registry
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>()
+    };
+    for cooldowns in all {
+        Cooldowns(cooldowns).prune().await;
+    }
+}
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum CooldownType {
@@ -13,7 +32,6 @@ pub enum CooldownType {
 
 #[derive(Clone, PartialEq)]
 pub struct Cooldown {
-    duration: Duration,
     expire: SystemTime,
     key: Option<String>,
     cooldown_info: CooldownType,
@@ -22,7 +40,6 @@ pub struct Cooldown {
 impl Cooldown {
     pub fn new(duration: Duration, key: Option<String>, cooldown_info: CooldownType) -> Cooldown {
         Cooldown {
-            duration,
             expire: SystemTime::now() + duration,
             key,
             cooldown_info,
@@ -30,19 +47,26 @@ impl Cooldown {
     }
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct Cooldowns(Arc<Mutex<Vec<Cooldown>>>);
 
+impl Default for Cooldowns {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Cooldowns {
     pub fn new() -> Self {
-        Self::default()
+        let inner = Arc::new(Mutex::new(Vec::new()));
+        REGISTRY.lock().unwrap().push(Arc::downgrade(&inner));
+        Self(inner)
     }
-    async fn remove_cooldown(inner: Arc<Mutex<Vec<Cooldown>>>, cooldown: Cooldown) {
-        sleep(cooldown.duration).await;
-        let mut c = inner.lock().await;
-        if let Some(i) = c.iter().position(|v| v == &cooldown) {
-            c.swap_remove(i);
-        }
+
+    /// Forgets the cooldowns that ran out. They are ignored either way, this only frees memory.
+    pub async fn prune(&self) {
+        let now = SystemTime::now();
+        self.0.lock().await.retain(|cooldown| cooldown.expire > now);
     }
 
     async fn add_cooldown(
@@ -52,11 +76,7 @@ impl Cooldowns {
         cooldown_type: CooldownType,
     ) {
         let mut c = self.0.lock().await;
-        let cooldown = Cooldown::new(duration, key, cooldown_type);
-        (*c).push(cooldown.clone());
-        drop(c);
-        // Make the cooldown automatically expire in the time
-        tokio::spawn(Cooldowns::remove_cooldown(self.0.clone(), cooldown));
+        (*c).push(Cooldown::new(duration, key, cooldown_type));
     }
     pub async fn add_global_cooldown(&self, duration: Duration) {
         self.add_cooldown(duration, None, CooldownType::Global)
@@ -76,10 +96,12 @@ impl Cooldowns {
     }
 
     async fn has_cooldown(&self, key: Option<String>, user: UserId) -> Option<Duration> {
+        let now = SystemTime::now();
         self.0
             .lock()
             .await
             .iter()
+            .filter(|cooldown| cooldown.expire > now)
             .find(|cooldown| {
                 if key != cooldown.key && cooldown.key.is_some() {
                     return false;
@@ -89,9 +111,7 @@ impl Cooldowns {
                     CooldownType::User(uid) => uid == user,
                 }
             })
-            .map(|cooldown| {
-                cooldown.duration - (cooldown.expire - cooldown.duration).elapsed().unwrap()
-            })
+            .map(|cooldown| cooldown.expire.duration_since(now).unwrap_or_default())
     }
 
     pub async fn check_cooldown(&self, user: UserId) -> Option<Duration> {
@@ -101,3 +121,27 @@ impl Cooldowns {
         self.has_cooldown(Some(key), user).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn expired_cooldowns_are_ignored() {
+        let cooldowns = Cooldowns::new();
+        let user = UserId(1);
+        cooldowns.add_user_cooldown(Duration::ZERO, user).await;
+        cooldowns
+            .add_user_cooldown_key(String::from("key"), Duration::from_secs(60), user)
+            .await;
+        assert!(cooldowns.check_cooldown(user).await.is_none());
+        assert!(cooldowns
+            .check_cooldown_key(user, String::from("key"))
+            .await
+            .is_some());
+        assert!(cooldowns.check_cooldown(UserId(2)).await.is_none());
+
+        prune_all().await;
+        assert_eq!(cooldowns.0.lock().await.len(), 1);
+    }
+}
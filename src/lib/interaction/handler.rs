@@ -9,7 +9,8 @@ use serenity::{
     client::Context,
     model::application::interaction::{
         application_command::ApplicationCommandInteraction, autocomplete::AutocompleteInteraction,
-        message_component::MessageComponentInteraction, MessageFlags,
+        message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+        MessageFlags,
     },
     model::prelude::*,
 };
@@ -256,6 +257,79 @@ pub trait InteractionHandler: Send + Sync {
         Ok(None)
     }
 
+    async fn on_modal(
+        &self,
+        ctx: &Context,
+        modal: &ModalSubmitInteraction,
+        args: &[&str],
+    ) -> crate::Result<()> {
+        if let Err(no_permissions) = self
+            .verify_execution(ctx, &modal.user, &modal.member, &modal.channel_id)
+            .await
+        {
+            let _ = modal
+                .respond(ctx, no_permissions.0)
+                .await
+                .map_err(|err| tracing::error!("Sending no permissions response failed: {}", err));
+            return Ok(());
+        }
+
+        let initial_response = self.get_initial_response(modal.kind);
+        if let Some(initial_response) = initial_response.clone() {
+            modal.create_response(ctx, initial_response).await?;
+        }
+
+        let res = self._on_modal(ctx, modal, args).await;
+        let resp = match res.as_ref() {
+            Ok(resp) => resp.clone(),
+            Err(err) => match err.downcast_ref::<Box<ErrorResponse>>() {
+                Some(err) => Some(err.0.clone()),
+                None => Some(self.unexpected_error().0),
+            },
+        };
+
+        if let Some(resp) = resp {
+            let _ = match initial_response {
+                Some(_) => modal.edit_response(ctx, resp).await,
+                None => modal.respond(ctx, resp).await,
+            }
+            .map_err(|err| tracing::error!("Sending InteractionErrorResponse failed: {}", err));
+        }
+
+        if let Err(err) = res {
+            if err.downcast_ref::<Box<ErrorResponse>>().is_none() {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn _on_modal(
+        &self,
+        ctx: &Context,
+        modal: &ModalSubmitInteraction,
+        args: &[&str],
+    ) -> InteractionResult {
+        let res = AssertUnwindSafe(self.handle_modal(ctx, modal, args))
+            .catch_unwind()
+            .await;
+        match res {
+            Err(_) => Err(self.unexpected_error())?, // on panic
+            Ok(v) => v,
+        }
+    }
+
+    /// Called with the arguments of the modal's custom id, like components
+    async fn handle_modal(
+        &self,
+        _ctx: &Context,
+        _modal: &ModalSubmitInteraction,
+        _args: &[&str],
+    ) -> InteractionResult {
+        Ok(None)
+    }
+
     async fn on_autocomplete(
         &self,
         ctx: &Context,
@@ -2,8 +2,11 @@ use serde_json::{Map, Value};
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction as ACI;
 use serenity::model::application::interaction::application_command::CommandDataOption as CDO;
 use serenity::model::application::interaction::autocomplete::AutocompleteInteraction as AI;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction as MSI;
+use serenity::model::prelude::component::ActionRowComponent;
 
-/// Extracts options from an `ApplicationCommandInteraction`
+/// Extracts options from an `ApplicationCommandInteraction`, or the inputs of a submitted modal
+/// by their custom id
 /// Delegates [`serde_json::Value`] methods
 pub trait InteractOpts: Sized {
     fn get_map<T>(&self, query: impl AsRef<str>, map: impl FnOnce(Value) -> T) -> Option<T>;
@@ -89,3 +92,19 @@ impl InteractOpts for CDO {
             .and_then(|x| x.value.clone().map(map))
     }
 }
+
+impl InteractOpts for MSI {
+    fn get_map<T>(&self, query: impl AsRef<str>, map: impl FnOnce(Value) -> T) -> Option<T> {
+        self.data
+            .components
+            .iter()
+            .flat_map(|row| row.components.iter())
+            .find_map(|component| match component {
+                ActionRowComponent::InputText(input) if input.custom_id == query.as_ref() => {
+                    Some(Value::String(input.value.clone()))
+                }
+                _ => None,
+            })
+            .map(map)
+    }
+}
@@ -4,7 +4,8 @@ use serenity::{
     http::Http,
     model::application::interaction::{
         application_command::ApplicationCommandInteraction,
-        message_component::MessageComponentInteraction, MessageFlags,
+        message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+        MessageFlags,
     },
     model::prelude::*,
     Result,
@@ -134,3 +135,52 @@ impl RespondableInteraction for MessageComponentInteraction {
         Ok(())
     }
 }
+
+#[async_trait]
+impl RespondableInteraction for ModalSubmitInteraction {
+    async fn create_response<'a>(
+        &self,
+        http: impl AsRef<Http> + Send + Sync,
+        resp: CreateInteractionResponse<'a>,
+    ) -> Result<()> {
+        self.create_interaction_response(http.as_ref(), |d| {
+            *d = resp;
+            d
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn respond<'a>(
+        &self,
+        http: impl AsRef<Http> + Send + Sync,
+        resp: CreateInteractionResponseData<'a>,
+    ) -> Result<()> {
+        self.create_interaction_response(http.as_ref(), |d| {
+            d.interaction_response_data(|d| {
+                d.0 = resp.0;
+                d.1 = resp.1;
+                if !d.0.contains_key("flags") {
+                    d.flags(MessageFlags::EPHEMERAL);
+                }
+                d
+            });
+            d
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn edit_response<'a>(
+        &self,
+        http: impl AsRef<Http> + Send + Sync,
+        resp: CreateInteractionResponseData<'a>,
+    ) -> Result<()> {
+        self.edit_original_interaction_response(http.as_ref(), |d| {
+            d.0 = resp.0;
+            d
+        })
+        .await?;
+        Ok(())
+    }
+}
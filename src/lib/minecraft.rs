@@ -0,0 +1,118 @@
+use std::{collections::HashMap, time::Duration};
+
+use serde::Deserialize;
+use serenity::async_trait;
+
+/// A Minecraft account. Names can change, the uuid always stays the same.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Profile {
+    /// Without dashes, like Mojang returns it
+    #[serde(rename = "id")]
+    pub uuid: String,
+    /// The current name with its proper capitalization
+    pub name: String,
+}
+
+/// Turns in-game names into the accounts behind them
+#[async_trait]
+pub trait ProfileResolver: Send + Sync {
+    /// Returns None if no account has that name
+    async fn resolve(&self, ign: &str) -> crate::Result<Option<Profile>>;
+}
+
+/// Asks the Mojang API
+pub struct MojangResolver {
+    client: reqwest::Client,
+}
+
+const MOJANG_PROFILE_URL: &str = "https://api.mojang.com/users/profiles/minecraft/";
+/// Names are resolved while tickets are opened, which shouldn't wait on a slow Mojang API
+const MOJANG_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl MojangResolver {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(MOJANG_TIMEOUT)
+                .build()
+                .expect("Could not build the Mojang API client"),
+        }
+    }
+}
+
+impl Default for MojangResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ProfileResolver for MojangResolver {
+    async fn resolve(&self, ign: &str) -> crate::Result<Option<Profile>> {
+        let resp = self
+            .client
+            .get(format!("{}{}", MOJANG_PROFILE_URL, ign))
+            .send()
+            .await?;
+        // Unknown names used to get an empty 204 and get a 404 now
+        if matches!(
+            resp.status(),
+            reqwest::StatusCode::NO_CONTENT | reqwest::StatusCode::NOT_FOUND
+        ) {
+            return Ok(None);
+        }
+        Ok(Some(resp.error_for_status()?.json().await?))
+    }
+}
+
+/// Resolves a fixed set of accounts without any requests, for tests
+#[derive(Default)]
+pub struct StubResolver {
+    profiles: HashMap<String, Profile>,
+}
+
+impl StubResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, uuid: &str) -> Self {
+        self.profiles.insert(
+            name.to_lowercase(),
+            Profile {
+                uuid: uuid.to_string(),
+                name: name.to_string(),
+            },
+        );
+        self
+    }
+}
+
+#[async_trait]
+impl ProfileResolver for StubResolver {
+    async fn resolve(&self, ign: &str) -> crate::Result<Option<Profile>> {
+        Ok(self.profiles.get(&ign.to_lowercase()).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mojang_response() {
+        let profile: Profile =
+            serde_json::from_str(r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch"}"#)
+                .unwrap();
+        assert_eq!(profile.uuid, "069a79f444e94726a5befca90e38aaf5");
+        assert_eq!(profile.name, "Notch");
+    }
+
+    #[tokio::test]
+    async fn stub_ignores_case() {
+        let resolver = StubResolver::new().with("Notch", "069a79f444e94726a5befca90e38aaf5");
+        let profile = resolver.resolve("nOtCh").await.unwrap().unwrap();
+        assert_eq!(profile.name, "Notch");
+        assert!(resolver.resolve("jeb_").await.unwrap().is_none());
+    }
+}
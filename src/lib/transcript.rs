@@ -0,0 +1,311 @@
+//! This module renders channel messages as a standalone HTML page and bundles it with the
+//! attachments of the messages into a zip archive
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::io::{Cursor, Write};
+
+use serenity::model::{
+    channel::{Attachment, Embed, Message, ReactionType},
+    id::AttachmentId,
+};
+use zip::{write::FileOptions, ZipWriter};
+
+const STYLE: &str = "\
+body{background:#313338;color:#dbdee1;font-family:sans-serif;margin:0;padding:16px}\
+h1{color:#f2f3f5;font-size:20px}\
+.message{display:flex;gap:12px;padding:6px 0}\
+.avatar{width:40px;height:40px;border-radius:50%}\
+.author{color:#f2f3f5;font-weight:600}\
+.bot{background:#5865f2;border-radius:3px;color:#fff;font-size:10px;margin-left:4px;padding:1px 4px}\
+.time{color:#949ba4;font-size:12px;margin-left:6px}\
+.content{white-space:pre-wrap;word-break:break-word}\
+.reply{color:#949ba4;font-size:13px;margin-bottom:2px}\
+.embed{background:#2b2d31;border-left:4px solid #1e1f22;border-radius:4px;margin-top:4px;max-width:520px;padding:8px 12px}\
+.embed .title{color:#f2f3f5;font-weight:600}\
+.field{margin-top:6px}.field .name{font-weight:600}\
+.footer{color:#949ba4;font-size:12px;margin-top:6px}\
+.attachment img,.embed img{border-radius:4px;display:block;margin-top:4px;max-width:400px}\
+.reactions{display:flex;gap:4px;margin-top:4px}\
+.reaction{background:#2b2d31;border-radius:8px;font-size:13px;padding:2px 6px}\
+.reaction img{height:16px;vertical-align:middle}";
+
+/// Escapes text so it can be put into HTML as is
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Where the attachment is stored inside the archive
+pub fn attachment_path(attachment: &Attachment) -> String {
+    let filename = attachment
+        .filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    format!("attachments/{}_{}", attachment.id, filename)
+}
+
+fn render_attachment(html: &mut String, attachment: &Attachment, archived: bool) {
+    // Attachments that couldn't be archived still link to Discord, for as long as it keeps them
+    let src = if archived {
+        attachment_path(attachment)
+    } else {
+        attachment.url.clone()
+    };
+    let is_image = attachment
+        .content_type
+        .as_ref()
+        .map_or(attachment.width.is_some(), |kind| {
+            kind.starts_with("image/")
+        });
+    html.push_str("<div class=\"attachment\">");
+    if is_image {
+        let _ = write!(
+            html,
+            "<a href=\"{0}\"><img src=\"{0}\" alt=\"{1}\"></a>",
+            escape(&src),
+            escape(&attachment.filename)
+        );
+    } else {
+        let _ = write!(
+            html,
+            "📎 <a href=\"{}\">{}</a> ({} KB)",
+            escape(&src),
+            escape(&attachment.filename),
+            attachment.size / 1024
+        );
+    }
+    if !archived {
+        html.push_str(" <i>(not archived)</i>");
+    }
+    html.push_str("</div>");
+}
+
+fn render_embed(html: &mut String, embed: &Embed) {
+    let _ = write!(
+        html,
+        "<div class=\"embed\" style=\"border-color:#{}\">",
+        embed.colour.map_or(String::from("1e1f22"), |c| c.hex())
+    );
+    if let Some(author) = &embed.author {
+        let _ = write!(html, "<div class=\"author\">{}</div>", escape(&author.name));
+    }
+    if let Some(title) = &embed.title {
+        let _ = write!(html, "<div class=\"title\">{}</div>", escape(title));
+    }
+    if let Some(description) = &embed.description {
+        let _ = write!(html, "<div class=\"content\">{}</div>", escape(description));
+    }
+    for field in &embed.fields {
+        let _ = write!(
+            html,
+            "<div class=\"field\"><div class=\"name\">{}</div><div class=\"content\">{}</div></div>",
+            escape(&field.name),
+            escape(&field.value)
+        );
+    }
+    if let Some(image) = &embed.image {
+        let _ = write!(html, "<img src=\"{}\">", escape(&image.url));
+    }
+    if let Some(footer) = &embed.footer {
+        let _ = write!(html, "<div class=\"footer\">{}</div>", escape(&footer.text));
+    }
+    html.push_str("</div>");
+}
+
+fn render_reaction(html: &mut String, reaction_type: &ReactionType, count: u64) {
+    let emoji = match reaction_type {
+        ReactionType::Custom { animated, id, name } => format!(
+            "<img src=\"https://cdn.discordapp.com/emojis/{}.{}\" alt=\":{}:\">",
+            id,
+            if *animated { "gif" } else { "png" },
+            escape(name.as_deref().unwrap_or_default())
+        ),
+        ReactionType::Unicode(emoji) => escape(emoji),
+        _ => String::from("?"),
+    };
+    let _ = write!(html, "<span class=\"reaction\">{} {}</span>", emoji, count);
+}
+
+fn render_message(html: &mut String, message: &Message, archived: &HashSet<AttachmentId>) {
+    html.push_str("<div class=\"message\">");
+    let _ = write!(
+        html,
+        "<img class=\"avatar\" src=\"{}\"><div>",
+        escape(&message.author.face())
+    );
+    if let Some(reply) = &message.referenced_message {
+        let snippet = reply.content.chars().take(100).collect::<String>();
+        let _ = write!(
+            html,
+            "<div class=\"reply\">↪ {}: {}</div>",
+            escape(&reply.author.tag()),
+            escape(&snippet)
+        );
+    }
+    let _ = write!(
+        html,
+        "<span class=\"author\" title=\"{}\">{}</span>{}<span class=\"time\">{}{}</span>",
+        message.author.id,
+        escape(&message.author.tag()),
+        if message.author.bot {
+            "<span class=\"bot\">BOT</span>"
+        } else {
+            ""
+        },
+        message.timestamp,
+        message
+            .edited_timestamp
+            .map_or(String::new(), |edited| format!(" (edited {})", edited))
+    );
+    if !message.content.is_empty() {
+        let _ = write!(
+            html,
+            "<div class=\"content\">{}</div>",
+            escape(&message.content)
+        );
+    }
+    for attachment in &message.attachments {
+        render_attachment(html, attachment, archived.contains(&attachment.id));
+    }
+    for embed in &message.embeds {
+        render_embed(html, embed);
+    }
+    if !message.reactions.is_empty() {
+        html.push_str("<div class=\"reactions\">");
+        for reaction in &message.reactions {
+            render_reaction(html, &reaction.reaction_type, reaction.count);
+        }
+        html.push_str("</div>");
+    }
+    html.push_str("</div></div>");
+}
+
+/// Renders the messages oldest first. Attachments in `archived` are linked to their
+/// [`attachment_path`], the others to Discord.
+pub fn render(title: &str, messages: &[Message], archived: &HashSet<AttachmentId>) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{0}</title><style>{1}</style>\
+        </head><body><h1>{0}</h1>",
+        escape(title),
+        STYLE
+    );
+    for message in messages {
+        render_message(&mut html, message, archived);
+    }
+    html.push_str("</body></html>");
+    html
+}
+
+/// Zips the transcript together with the archived attachments, given as their path and data
+pub fn archive(html: &str, attachments: &[(String, Vec<u8>)]) -> crate::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default();
+    zip.start_file("transcript.html", options)?;
+    zip.write_all(html.as_bytes())?;
+    for (path, data) in attachments {
+        zip.start_file(path.as_str(), options)?;
+        zip.write_all(data)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use serde_json::json;
+    use zip::ZipArchive;
+
+    use super::*;
+
+    fn message(id: u64, content: &str, extra: serde_json::Value) -> Message {
+        let mut value = json!({
+            "id": id.to_string(),
+            "channel_id": "1",
+            "author": {"id": "2", "username": "Some<User>", "discriminator": "0001", "avatar": null},
+            "content": content,
+            "timestamp": "2022-05-01T12:00:00+00:00",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 0,
+        });
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn renders_messages() {
+        let original = message(3, "hello", json!({}));
+        let reply = message(
+            4,
+            "<script>alert(1)</script>",
+            json!({
+                "referenced_message": serde_json::to_value(&original).unwrap(),
+                "reactions": [{"count": 2, "me": false, "emoji": {"id": null, "name": "👍"}}],
+                "embeds": [{"title": "Proof", "fields": [{"name": "Ign", "value": "Notch", "inline": false}]}],
+                "attachments": [
+                    {"id": "5", "filename": "clip.mp4", "size": 2048, "url": "https://cdn/clip.mp4", "proxy_url": "https://proxy/clip.mp4", "height": null, "width": null},
+                    {"id": "6", "filename": "../shot 1.png", "size": 10, "url": "https://cdn/shot.png", "proxy_url": "https://proxy/shot.png", "height": 1, "width": 1, "content_type": "image/png"},
+                ],
+            }),
+        );
+        let archived = HashSet::from([AttachmentId(6)]);
+        let html = render("Ticket", &[original, reply], &archived);
+
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("Some&lt;User&gt;#0001"));
+        assert!(html.contains("↪ Some&lt;User&gt;#0001: hello"));
+        assert!(html.contains("👍 2"));
+        assert!(html.contains("Proof") && html.contains("Notch"));
+        assert!(html.contains("https://cdn/clip.mp4\">clip.mp4</a> (2 KB) <i>(not archived)</i>"));
+        assert!(html.contains("<img src=\"attachments/6_.._shot_1.png\""));
+    }
+
+    #[test]
+    fn archives_attachments() {
+        let attachments = [(String::from("attachments/6_shot.png"), vec![1, 2, 3])];
+        let data = archive("<html></html>", &attachments).unwrap();
+
+        let mut zip = ZipArchive::new(Cursor::new(data)).unwrap();
+        let mut html = String::new();
+        zip.by_name("transcript.html")
+            .unwrap()
+            .read_to_string(&mut html)
+            .unwrap();
+        assert_eq!(html, "<html></html>");
+        let mut image = Vec::new();
+        zip.by_name("attachments/6_shot.png")
+            .unwrap()
+            .read_to_end(&mut image)
+            .unwrap();
+        assert_eq!(image, vec![1, 2, 3]);
+    }
+}
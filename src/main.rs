@@ -2,7 +2,7 @@ use serenity::model::gateway::GatewayIntents;
 use serenity::Client;
 use tracing_subscriber::filter::LevelFilter;
 
-use crate::consts::{CONFIG, SECRETS};
+use crate::consts::{CONFIG, DATABASE, SECRETS};
 use crate::handler::Handler;
 use bridge_scrims::Result;
 
@@ -22,6 +22,9 @@ async fn main() {
         .with_max_level(LevelFilter::INFO)
         .init();
 
+    // Migrate the database before connecting so an unknown schema stops the bot from booting
+    lazy_static::initialize(&DATABASE);
+
     let intents = GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT
         | GatewayIntents::GUILD_MEMBERS
@@ -1,26 +1,128 @@
 use std::fmt::Display;
 use std::num::ParseIntError;
+use std::str::FromStr;
 
+use serde::{Deserialize, Serialize};
 use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
 use time::OffsetDateTime;
 
 pub struct ScrimUnban {
     pub id: u64,
+    /// None for permanent bans
     pub date: Option<OffsetDateTime>,
     pub roles: Ids,
+    /// The ban was lifted while the user wasn't in the server and only their roles are left to
+    /// give back once they rejoin
+    pub logged: bool,
 }
 
 impl ScrimUnban {
     pub fn is_expired(&self) -> bool {
-        self.date
-            .map_or(true, |date| date <= OffsetDateTime::now_utc())
+        self.logged
+            || self
+                .date
+                .map_or(false, |date| date <= OffsetDateTime::now_utc())
     }
 
     pub fn was_logged(&self) -> bool {
+        self.logged
+    }
+
+    pub fn is_permanent(&self) -> bool {
         self.date.is_none()
     }
 }
 
+/// An entry in the scrim ban history, which is kept after the ban is over
+pub struct ScrimBan {
+    pub id: u64,
+    /// User ID of the banned person
+    pub user: u64,
+    /// Unknown for bans issued before the history was recorded
+    pub staff: Option<u64>,
+    pub reason: String,
+    /// Unknown for bans issued before the history was recorded
+    pub issued_at: Option<OffsetDateTime>,
+    pub expires_at: Option<OffsetDateTime>,
+    /// The staff member that lifted the ban, none if it ran out
+    pub revoked_by: Option<u64>,
+    pub revoke_reason: Option<String>,
+    pub revoked_at: Option<OffsetDateTime>,
+}
+
+impl ScrimBan {
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+            && self
+                .expires_at
+                .map_or(true, |date| date > OffsetDateTime::now_utc())
+    }
+}
+
+/// A change to the expiry or reason of a scrim ban after it was issued
+pub struct ScrimBanEdit {
+    pub staff: u64,
+    pub edited_at: OffsetDateTime,
+    pub old_expires_at: Option<OffsetDateTime>,
+    pub new_expires_at: Option<OffsetDateTime>,
+    pub old_reason: String,
+    pub new_reason: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AppealStatus {
+    Pending,
+    Accepted,
+    Denied,
+    /// The ban was shortened instead of lifted
+    Reduced,
+}
+
+impl AppealStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Accepted => "accepted",
+            Self::Denied => "denied",
+            Self::Reduced => "reduced",
+        }
+    }
+}
+
+impl Display for AppealStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AppealStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "accepted" => Ok(Self::Accepted),
+            "denied" => Ok(Self::Denied),
+            "reduced" => Ok(Self::Reduced),
+            _ => Err(format!("unknown appeal status `{}`", s)),
+        }
+    }
+}
+
+/// A user's request to have one of their scrim bans lifted
+pub struct Appeal {
+    pub id: u64,
+    /// The id of the [`ScrimBan`] that is appealed, every ban can be appealed once
+    pub ban: u64,
+    pub user: u64,
+    /// What the user wrote
+    pub appeal: String,
+    pub created_at: OffsetDateTime,
+    pub status: AppealStatus,
+    pub reviewed_by: Option<u64>,
+    pub reviewed_at: Option<OffsetDateTime>,
+}
+
 pub struct Screenshare {
     /// Channel ID of the ticket
     pub id: u64,
@@ -28,6 +130,130 @@ pub struct Screenshare {
     pub creator: u64,
     /// User ID of the person being screenshared
     pub in_question: u64,
+    /// When the ticket was opened
+    pub created_at: OffsetDateTime,
+    /// The screensharer that claimed the ticket
+    pub assignee: Option<u64>,
+}
+
+/// How a screenshare ended, chosen by the screensharer closing the ticket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreenshareOutcome {
+    /// Nothing was found
+    Cleared,
+    /// The player admitted to cheating
+    Admitted,
+    /// The player refused to be screenshared
+    Refused,
+    /// Something was found and the player got banned
+    Banned,
+    /// Nobody froze the player before the ticket timed out, never chosen by hand
+    TimedOut,
+}
+
+impl ScreenshareOutcome {
+    /// The outcomes a screensharer can choose from
+    pub const CHOOSABLE: [Self; 4] = [Self::Cleared, Self::Admitted, Self::Refused, Self::Banned];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cleared => "cleared",
+            Self::Admitted => "admitted",
+            Self::Refused => "refused",
+            Self::Banned => "banned",
+            Self::TimedOut => "timed_out",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Cleared => "Cleared",
+            Self::Admitted => "Admitted",
+            Self::Refused => "Refused",
+            Self::Banned => "Banned",
+            Self::TimedOut => "Timed Out",
+        }
+    }
+}
+
+impl Display for ScreenshareOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ScreenshareOutcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cleared" => Ok(Self::Cleared),
+            "admitted" => Ok(Self::Admitted),
+            "refused" => Ok(Self::Refused),
+            "banned" => Ok(Self::Banned),
+            "timed_out" => Ok(Self::TimedOut),
+            _ => Err(format!("unknown screenshare outcome `{}`", s)),
+        }
+    }
+}
+
+/// A screenshare ticket after it was closed
+pub struct ClosedScreenshare {
+    pub closer: u64,
+    /// The screensharer that had claimed the ticket
+    pub assignee: Option<u64>,
+    pub outcome: ScreenshareOutcome,
+    pub notes: Option<String>,
+    pub closed_at: OffsetDateTime,
+}
+
+/// What a screensharer did, counted towards their stats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreenshareActionKind {
+    Freeze,
+    Unfreeze,
+}
+
+impl ScreenshareActionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Freeze => "freeze",
+            Self::Unfreeze => "unfreeze",
+        }
+    }
+}
+
+impl Display for ScreenshareActionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ScreenshareActionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "freeze" => Ok(Self::Freeze),
+            "unfreeze" => Ok(Self::Unfreeze),
+            _ => Err(format!("unknown screenshare action `{}`", s)),
+        }
+    }
+}
+
+/// An action counted towards the stats of the window it was fetched for
+pub struct ScreenshareAction {
+    pub staff: u64,
+    pub kind: ScreenshareActionKind,
+    /// Seconds between the ticket being opened and the user getting frozen, for freezes of
+    /// users with a ticket
+    pub response: Option<u64>,
+}
+
+/// Someone that reported the user in question of a ticket after it was opened by someone else
+pub struct ScreenshareReporter {
+    pub user: u64,
+    pub added_at: OffsetDateTime,
 }
 
 pub struct Freeze {
@@ -37,6 +263,11 @@ pub struct Freeze {
     pub roles: Vec<RoleId>,
     /// Time when they were frozen
     pub time: OffsetDateTime,
+    /// When they admitted to cheating, if they did
+    pub admitted_at: Option<OffsetDateTime>,
+    /// The private channels of the freeze, None for freezes from before they had their own
+    pub text_channel: Option<ChannelId>,
+    pub voice_channel: Option<ChannelId>,
 }
 
 #[derive(Debug)]
@@ -92,6 +323,99 @@ pub struct CustomReaction {
     pub emoji: String,
 }
 
+/// What an account looked like when it was last seen joining or getting banned, so alts of
+/// banned users can be recognized after the banned account left
+pub struct KnownAccount {
+    pub id: u64,
+    pub name: String,
+    /// The avatar hash
+    pub avatar: Option<String>,
+    /// The invite code the account joined with, if it could be worked out
+    pub invite: Option<String>,
+}
+
+/// A Minecraft account a Discord user is known to play on
+pub struct MinecraftAccount {
+    pub user: u64,
+    pub ign: String,
+    /// Known once the name was resolved, names can be taken by another account after a rename
+    pub uuid: Option<String>,
+    /// Who linked the account, the user themselves or the staff member that opened a screenshare
+    pub linked_by: u64,
+    pub linked_at: OffsetDateTime,
+}
+
+impl MinecraftAccount {
+    /// Anyone can link any name to themselves, so only links made by someone else are trusted
+    pub fn is_verified(&self) -> bool {
+        self.linked_by != self.user
+    }
+}
+
+/// Something the scheduler runs at a set time, stored as JSON
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Job {
+    /// Lifts every scrim ban that ran out
+    ScrimUnbans,
+    /// Closes a screenshare ticket unless the user in question got frozen
+    TicketTimeout {
+        channel: u64,
+        in_question: u64,
+        creator: u64,
+    },
+    /// Reloads the custom reactions from the database
+    RefreshReactions,
+    /// Forgets command cooldowns that ran out
+    PruneCooldowns,
+    /// Pings screensharers if the user is still frozen without having joined their voice channel
+    FreezeDeadline {
+        user: u64,
+        /// When the user was frozen, so that a later freeze isn't mistaken for this one
+        frozen_at: i64,
+    },
+    /// Archives and deletes the channels of a freeze that ended
+    FreezeCleanup { user: u64, text: u64, voice: u64 },
+}
+
+impl Job {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ScrimUnbans => "ScrimUnbans",
+            Self::TicketTimeout { .. } => "TicketTimeout",
+            Self::RefreshReactions => "RefreshReactions",
+            Self::PruneCooldowns => "PruneCooldowns",
+            Self::FreezeDeadline { .. } => "FreezeDeadline",
+            Self::FreezeCleanup { .. } => "FreezeCleanup",
+        }
+    }
+
+    pub fn payload(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+impl FromStr for Job {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+pub struct ScheduledJob {
+    pub id: u64,
+    pub job: Job,
+    pub run_at: OffsetDateTime,
+    /// How often the job was started since it last succeeded
+    pub attempts: u64,
+    pub last_error: Option<String>,
+    /// Set while the job is running. If the bot stops before it finishes, it runs again after this.
+    pub locked_until: Option<OffsetDateTime>,
+    /// The scheduler gave up on the job, it only runs again when retried by hand
+    pub failed: bool,
+}
+
 pub struct Screensharer {
     pub id: u64,
     pub freezes: u64,